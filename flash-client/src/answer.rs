@@ -0,0 +1,198 @@
+use tui::{
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+//Which differences between a typed answer and the card are forgiven, set per deck.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnswerMatching {
+    pub ignore_case: bool,
+    pub ignore_whitespace: bool,
+    pub ignore_accents: bool,
+}
+
+impl Default for AnswerMatching {
+    fn default() -> Self {
+        AnswerMatching {
+            ignore_case: true,
+            ignore_whitespace: true,
+            ignore_accents: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Diff {
+    //Typed and expected agree on this character.
+    Correct(char),
+    //Expected but never typed.
+    Missing(char),
+    //Typed but not part of the answer.
+    Wrong(char),
+}
+
+impl AnswerMatching {
+    //What a character is compared as, accents are stripped by decomposing and dropping the combining marks.
+    fn key(&self, c: char) -> String {
+        if c.is_whitespace() {
+            return String::from(" ");
+        }
+        let mut key: String = if self.ignore_accents {
+            c.to_string()
+                .nfd()
+                .filter(|part| !is_combining_mark(*part))
+                .collect()
+        } else {
+            c.to_string()
+        };
+        if self.ignore_case {
+            key = key.to_lowercase();
+        }
+        key
+    }
+
+    //Character level diff from the longest common subsequence of the two answers.
+    pub fn diff(&self, typed: &str, expected: &str) -> Vec<Diff> {
+        let typed: Vec<char> = typed.trim().chars().collect();
+        let expected: Vec<char> = expected.trim().chars().collect();
+        let typed_keys: Vec<String> = typed.iter().map(|c| self.key(*c)).collect();
+        let expected_keys: Vec<String> = expected.iter().map(|c| self.key(*c)).collect();
+        let mut lengths = vec![vec![0usize; expected.len() + 1]; typed.len() + 1];
+        for i in (0..typed.len()).rev() {
+            for j in (0..expected.len()).rev() {
+                lengths[i][j] = if typed_keys[i] == expected_keys[j] {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+        let mut diffs = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < typed.len() || j < expected.len() {
+            if i < typed.len() && j < expected.len() && typed_keys[i] == expected_keys[j] {
+                diffs.push(Diff::Correct(expected[j]));
+                i += 1;
+                j += 1;
+            } else if j < expected.len()
+                && (i == typed.len() || lengths[i][j + 1] >= lengths[i + 1][j])
+            {
+                diffs.push(Diff::Missing(expected[j]));
+                j += 1;
+            } else {
+                diffs.push(Diff::Wrong(typed[i]));
+                i += 1;
+            }
+        }
+        if self.ignore_whitespace {
+            //Spaces left out still show up in the answer, extra ones are dropped.
+            diffs = diffs
+                .into_iter()
+                .filter(|diff| !matches!(diff, Diff::Wrong(c) if c.is_whitespace()))
+                .map(|diff| match diff {
+                    Diff::Missing(c) if c.is_whitespace() => Diff::Correct(c),
+                    other => other,
+                })
+                .collect();
+        }
+        diffs
+    }
+}
+
+pub fn is_correct(diffs: &[Diff]) -> bool {
+    diffs.iter().all(|diff| matches!(diff, Diff::Correct(_)))
+}
+
+pub fn as_spans(diffs: &[Diff]) -> Spans<'static> {
+    Spans::from(
+        diffs
+            .iter()
+            .map(|diff| match diff {
+                Diff::Correct(c) => Span::styled(c.to_string(), Style::default().fg(Color::Green)),
+                Diff::Missing(c) => Span::styled(
+                    c.to_string(),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::UNDERLINED),
+                ),
+                Diff::Wrong(c) => Span::styled(
+                    c.to_string(),
+                    Style::default()
+                        .fg(Color::Red)
+                        .add_modifier(Modifier::CROSSED_OUT),
+                ),
+            })
+            .collect::<Vec<Span>>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn correct(text: &str) -> Vec<Diff> {
+        text.chars().map(Diff::Correct).collect()
+    }
+
+    #[test]
+    fn matching_answer_is_correct() {
+        let diffs = AnswerMatching::default().diff("  paris ", "Paris");
+        assert_eq!(diffs, correct("Paris"));
+        assert!(is_correct(&diffs));
+    }
+
+    #[test]
+    fn case_counts_when_not_ignored() {
+        let matching = AnswerMatching {
+            ignore_case: false,
+            ..AnswerMatching::default()
+        };
+        let diffs = matching.diff("paris", "Paris");
+        let mut expected = vec![Diff::Missing('P'), Diff::Wrong('p')];
+        expected.extend(correct("aris"));
+        assert_eq!(diffs, expected);
+        assert!(!is_correct(&diffs));
+    }
+
+    #[test]
+    fn missing_and_wrong_characters() {
+        let matching = AnswerMatching::default();
+        let mut expected = correct("hel");
+        expected.extend([Diff::Missing('l'), Diff::Correct('o')]);
+        assert_eq!(matching.diff("helo", "hello"), expected);
+        let mut expected = correct("ca");
+        expected.extend([Diff::Wrong('r'), Diff::Correct('t')]);
+        assert_eq!(matching.diff("cart", "cat"), expected);
+        let mut expected = correct("do");
+        expected.push(Diff::Missing('g'));
+        assert_eq!(matching.diff("do", "dog"), expected);
+    }
+
+    #[test]
+    fn whitespace_is_forgiven() {
+        let matching = AnswerMatching::default();
+        assert_eq!(matching.diff("new   york", "New York"), correct("New York"));
+        assert_eq!(matching.diff("newyork", "New York"), correct("New York"));
+        let strict = AnswerMatching {
+            ignore_whitespace: false,
+            ..matching
+        };
+        let mut expected = correct("New");
+        expected.push(Diff::Missing(' '));
+        expected.extend(correct("York"));
+        assert_eq!(strict.diff("newyork", "New York"), expected);
+    }
+
+    #[test]
+    fn accents_are_stripped_when_ignored() {
+        let matching = AnswerMatching::default();
+        assert!(!is_correct(&matching.diff("cafe", "café")));
+        let lenient = AnswerMatching {
+            ignore_accents: true,
+            ..matching
+        };
+        assert_eq!(lenient.diff("cafe", "café"), correct("café"));
+        assert_eq!(lenient.diff("CAFÉ", "cafe"), correct("cafe"));
+    }
+}
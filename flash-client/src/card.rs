@@ -1,24 +1,95 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::OnceLock;
 use tui::layout::Alignment;
 use tui::text::Spans;
 use tui::widgets::{Block, Borders, Paragraph, Widget};
+use uuid::Uuid;
+
+use crate::{cloze, markdown, note_type::NoteType, scheduler::Schedule};
+
+//Which way round a card is asked, every variant keeps its own schedule.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+    #[default]
+    Forward,
+    Reverse,
+    //One of the numbered deletions of a cloze card.
+    Cloze(u32),
+    //One of the templates of the deck's note type, the first keeps the card's own schedule.
+    Template(u32),
+}
+
+impl Variant {
+    //Forward uses the card's own schedule, the others are stored under this key.
+    fn key(&self) -> String {
+        match self {
+            Variant::Forward => String::from("forward"),
+            Variant::Reverse => String::from("reverse"),
+            Variant::Cloze(number) => format!("c{}", number),
+            Variant::Template(number) => format!("t{}", number),
+        }
+    }
+
+    pub fn label(&self) -> Option<String> {
+        match self {
+            Variant::Forward => None,
+            Variant::Reverse => Some(String::from("reverse")),
+            Variant::Cloze(number) => Some(format!("cloze {}", number)),
+            Variant::Template(_) => None,
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct Card {
+    //Cards are saved under their id, cards from before ids were added get one when they are read.
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
     pub title: String,
     pub sections: Vec<String>,
     pub current_section: usize,
+    #[serde(default)]
+    pub schedule: Schedule,
+    #[serde(default)]
+    pub leech: bool,
+    #[serde(default)]
+    pub suspended: bool,
+    #[serde(default)]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variant_schedules: BTreeMap<String, Schedule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    //Which of the deck's sources the card was loaded from, only meaningful while it is loaded.
+    #[serde(skip)]
+    pub source: usize,
+    //The note type of the deck the card was loaded from, which names its sections.
+    #[serde(skip)]
+    pub note: Option<Rc<NoteType>>,
 }
 
 impl Card {
     pub fn new(title: String) -> Self {
         return Card {
+            id: Uuid::new_v4(),
             title: title,
             sections: Vec::new(),
             current_section: 0,
+            schedule: Schedule::default(),
+            leech: false,
+            suspended: false,
+            created: Some(Utc::now()),
+            variant_schedules: BTreeMap::new(),
+            tags: Vec::new(),
+            source: 0,
+            note: None,
         };
     }
     pub fn read_from_file(filepath: &Path) -> Result<Self, Box<dyn std::error::Error>> {
@@ -42,9 +113,9 @@ impl Card {
         }
     }
 
-    pub fn increment_section(&mut self) -> Option<usize> {
+    pub fn increment_section(&mut self, variant: Variant) -> Option<usize> {
         if let Some(i) = self.current_section.checked_add(1) {
-            if i >= self.sections.len() {
+            if i >= self.page_count(variant) {
                 return None;
             }
             self.current_section = i;
@@ -54,9 +125,9 @@ impl Card {
         }
     }
 
-    pub fn decrement_section(&mut self) -> Option<usize> {
+    pub fn decrement_section(&mut self, variant: Variant) -> Option<usize> {
         if let Some(i) = self.current_section.checked_sub(1) {
-            if i >= self.sections.len() {
+            if i >= self.page_count(variant) {
                 return None;
             }
             self.current_section = i;
@@ -66,32 +137,204 @@ impl Card {
         }
     }
 
-    pub fn as_widget(&self) -> impl Widget {
-        if !self.sections.is_empty() {
-            let text = Spans::from(self.sections[self.current_section].to_owned());
-            return Paragraph::new(text).block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title(self.pad_title())
-                    .title_alignment(Alignment::Center),
-            );
+    //Cards with cloze deletions give one item per number, only cards with exactly a front and a back can be turned around.
+    //Cards of a note type give one item per template instead.
+    pub fn variants(&self, bidirectional: bool) -> Vec<Variant> {
+        if let Some(note) = &self.note {
+            return (0..note.templates.len() as u32)
+                .map(Variant::Template)
+                .collect();
+        }
+        let mut numbers: Vec<u32> = self
+            .sections
+            .iter()
+            .flat_map(|section| cloze::numbers(section))
+            .collect();
+        numbers.sort();
+        numbers.dedup();
+        if !numbers.is_empty() {
+            numbers.into_iter().map(Variant::Cloze).collect()
+        } else if bidirectional && self.sections.len() == 2 {
+            vec![Variant::Forward, Variant::Reverse]
         } else {
-            let text = Spans::from("");
-            return Paragraph::new(text).block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title(self.pad_title())
-                    .title_alignment(Alignment::Center),
-            );
+            vec![Variant::Forward]
         }
     }
 
+    pub fn schedule(&self, variant: Variant) -> &Schedule {
+        static NEW_SCHEDULE: OnceLock<Schedule> = OnceLock::new();
+        match variant {
+            Variant::Forward | Variant::Template(0) => &self.schedule,
+            _ => self
+                .variant_schedules
+                .get(&variant.key())
+                .unwrap_or_else(|| NEW_SCHEDULE.get_or_init(Schedule::default)),
+        }
+    }
+
+    pub fn schedule_mut(&mut self, variant: Variant) -> &mut Schedule {
+        match variant {
+            Variant::Forward | Variant::Template(0) => &mut self.schedule,
+            _ => self.variant_schedules.entry(variant.key()).or_default(),
+        }
+    }
+
+    pub fn schedules(&self) -> impl Iterator<Item = &Schedule> {
+        std::iter::once(&self.schedule).chain(self.variant_schedules.values())
+    }
+
+    pub fn schedules_mut(&mut self) -> impl Iterator<Item = &mut Schedule> {
+        std::iter::once(&mut self.schedule).chain(self.variant_schedules.values_mut())
+    }
+
+    //The section shown at a position when the card is asked as the given variant.
+    //Cloze cards are shown whole rather than a section at a time, templates are rendered from the fields.
+    pub fn section(&self, variant: Variant, index: usize) -> Option<String> {
+        let index = match variant {
+            Variant::Forward => index,
+            Variant::Reverse => self.sections.len().checked_sub(index + 1)?,
+            Variant::Cloze(_) => return None,
+            Variant::Template(number) => {
+                let note = self.note.as_ref()?;
+                return match index {
+                    0 => note.front(number as usize, &self.sections),
+                    1 => note.back(number as usize, &self.sections),
+                    _ => None,
+                };
+            }
+        };
+        self.sections.get(index).cloned()
+    }
+
+    //A cloze or template item has two pages, the front and then the revealed back.
+    pub fn page_count(&self, variant: Variant) -> usize {
+        match variant {
+            Variant::Cloze(_) | Variant::Template(_) => 2,
+            _ => self.sections.len(),
+        }
+    }
+
+    //Templates are told apart by name when a note type has more than one.
+    pub fn variant_label(&self, variant: Variant) -> Option<String> {
+        match (variant, &self.note) {
+            (Variant::Template(number), Some(note)) if note.templates.len() > 1 => note
+                .templates
+                .get(number as usize)
+                .map(|template| template.name.to_lowercase()),
+            _ => variant.label(),
+        }
+    }
+
+    //The name of a section in the editor, which is its field name for cards of a note type.
+    pub fn section_name(&self, index: usize) -> Option<String> {
+        self.note
+            .as_ref()
+            .and_then(|note| note.field_name(index))
+            .map(|field| field.to_string())
+    }
+
+    //The text on screen, a template shows its back under the front once it is revealed.
+    //A cloze item shows every section, with its deletions filled in as it is rendered.
+    fn shown_pages(&self, variant: Variant) -> Vec<String> {
+        match variant {
+            Variant::Cloze(_) => self.sections.clone(),
+            Variant::Template(_) => (0..=self.current_section.min(1))
+                .filter_map(|index| self.section(variant, index))
+                .collect(),
+            _ => self
+                .section(variant, self.current_section)
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    //How far the code on screen can be scrolled sideways in a panel of the given width.
+    pub fn code_overflow(&self, variant: Variant, width: u16) -> usize {
+        let width = width.saturating_sub(2) as usize;
+        self.shown_pages(variant)
+            .iter()
+            .map(|page| markdown::overflow(page, width, self.cloze(variant)))
+            .max()
+            .unwrap_or(0)
+    }
+
+    //The cloze number being asked and whether it has been revealed yet.
+    fn cloze(&self, variant: Variant) -> Option<(u32, bool)> {
+        match variant {
+            Variant::Cloze(number) => Some((number, self.current_section > 0)),
+            _ => None,
+        }
+    }
+
+    //Sections are Markdown, laid out to the width of the panel inside its borders.
+    pub fn as_widget(&self, variant: Variant, width: u16, scroll: usize) -> impl Widget {
+        let width = width.saturating_sub(2) as usize;
+        let mut text: Vec<Spans> = Vec::new();
+        for page in self.shown_pages(variant) {
+            if !text.is_empty() {
+                text.push(Spans::default());
+            }
+            text.extend(markdown::render(&page, width, scroll, self.cloze(variant)));
+        }
+        Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(self.pad_title())
+                .title_alignment(Alignment::Center),
+        )
+    }
+
+    //A card can only be graded once every section has been shown.
+    pub fn is_revealed(&self, variant: Variant) -> bool {
+        self.current_section + 1 >= self.page_count(variant)
+    }
+
+    pub fn reveal(&mut self, variant: Variant) {
+        self.current_section = self.page_count(variant).saturating_sub(1);
+    }
+
+    //Everything after the first section, what a cloze hides or a template's back, which is what a typed answer is checked against.
+    pub fn answer(&self, variant: Variant) -> String {
+        if let Variant::Cloze(number) = variant {
+            return self
+                .sections
+                .iter()
+                .flat_map(|section| cloze::answers(section, number))
+                .collect::<Vec<String>>()
+                .join(" ");
+        }
+        if let Variant::Template(_) = variant {
+            return self
+                .section(variant, 1)
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ");
+        }
+        (1..self.sections.len())
+            .filter_map(|index| self.section(variant, index))
+            .map(|section| section.trim().to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
     pub fn len(&self) -> usize {
         return self.sections.len();
     }
 
     pub fn saved_name(&self) -> String {
-        return self.title.clone() + ".json";
+        return self.id.to_string() + ".json";
+    }
+
+    //Tags are typed as one line separated by spaces, a leading '#' is optional.
+    pub fn set_tags(&mut self, text: &str) {
+        self.tags.clear();
+        for tag in text.split_whitespace() {
+            let tag = tag.trim_start_matches('#').to_lowercase();
+            if !tag.is_empty() && !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
     }
 
     pub fn pad_title(&self) -> String {
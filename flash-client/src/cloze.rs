@@ -0,0 +1,202 @@
+use tui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+
+//A piece of a section, either plain text or a deletion like {{c1::answer::hint}}.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Deletion {
+        number: u32,
+        answer: String,
+        hint: Option<String>,
+    },
+}
+
+//Unfinished or malformed markers are left in as plain text.
+fn parse(text: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{c") {
+        let after = &rest[start + 3..];
+        let digits = after.chars().take_while(|c| c.is_ascii_digit()).count();
+        let parsed = after[..digits].parse::<u32>().ok().and_then(|number| {
+            let body = after[digits..].strip_prefix("::")?;
+            let end = body.find("}}")?;
+            //An unclosed marker doesn't swallow the next one.
+            if body[..end].contains("{{c") {
+                return None;
+            }
+            let mut fields = body[..end].splitn(2, "::");
+            let answer = fields.next().unwrap_or_default().to_string();
+            let hint = fields.next().map(|hint| hint.to_string());
+            Some((number, answer, hint, start + 3 + digits + 2 + end + 2))
+        });
+        match parsed {
+            Some((number, answer, hint, consumed)) => {
+                if start > 0 {
+                    parts.push(Part::Text(rest[..start].to_string()));
+                }
+                parts.push(Part::Deletion {
+                    number,
+                    answer,
+                    hint,
+                });
+                rest = &rest[consumed..];
+            }
+            None => {
+                parts.push(Part::Text(rest[..start + 3].to_string()));
+                rest = &rest[start + 3..];
+            }
+        }
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Text(rest.to_string()));
+    }
+    parts
+}
+
+//The distinct cloze numbers in a section, each one becomes its own study item.
+pub fn numbers(text: &str) -> Vec<u32> {
+    let mut numbers: Vec<u32> = parse(text)
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Deletion { number, .. } => Some(number),
+            Part::Text(_) => None,
+        })
+        .collect();
+    numbers.sort();
+    numbers.dedup();
+    numbers
+}
+
+//What was hidden for the given cloze number.
+pub fn answers(text: &str, active: u32) -> Vec<String> {
+    parse(text)
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Deletion { number, answer, .. } if number == active => Some(answer),
+            _ => None,
+        })
+        .collect()
+}
+
+//Hides the active deletions as [...], or their hint, other numbers read as plain text.
+//The text keeps the style it is given, deletions are picked out on top of it.
+pub fn render(text: &str, active: u32, revealed: bool, style: Style) -> Vec<Span<'static>> {
+    let highlight = style.fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    parse(text)
+        .into_iter()
+        .map(|part| match part {
+            Part::Text(text) => Span::styled(text, style),
+            Part::Deletion { number, answer, .. } if number != active => {
+                Span::styled(answer, style)
+            }
+            Part::Deletion { answer, .. } if revealed => Span::styled(answer, highlight),
+            Part::Deletion { hint, .. } => Span::styled(
+                format!("[{}]", hint.unwrap_or_else(|| String::from("..."))),
+                highlight,
+            ),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(spans: Vec<Span>) -> Vec<String> {
+        spans
+            .into_iter()
+            .map(|span| span.content.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn parses_deletions_and_hints() {
+        assert_eq!(
+            parse("{{c1::Paris}} is in {{c2::France::country}}."),
+            vec![
+                Part::Deletion {
+                    number: 1,
+                    answer: String::from("Paris"),
+                    hint: None,
+                },
+                Part::Text(String::from(" is in ")),
+                Part::Deletion {
+                    number: 2,
+                    answer: String::from("France"),
+                    hint: Some(String::from("country")),
+                },
+                Part::Text(String::from(".")),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_markers_stay_as_text() {
+        let joined = |text: &str| {
+            parse(text)
+                .into_iter()
+                .map(|part| match part {
+                    Part::Text(text) => text,
+                    Part::Deletion { .. } => panic!("{} has no deletion", text),
+                })
+                .collect::<String>()
+        };
+        for text in [
+            "{{c1::Paris",
+            "{{c::Paris}}",
+            "{{cx::Paris}}",
+            "{{c1:Paris}}",
+            "{{c1",
+        ] {
+            assert_eq!(joined(text), text);
+        }
+        let parts = parse("{{c1::open {{c2::Rome}}");
+        assert_eq!(
+            parts.last(),
+            Some(&Part::Deletion {
+                number: 2,
+                answer: String::from("Rome"),
+                hint: None,
+            })
+        );
+        assert_eq!(numbers("{{c1::open {{c2::Rome}}"), vec![2]);
+    }
+
+    #[test]
+    fn numbers_are_sorted_and_distinct() {
+        assert_eq!(
+            numbers("{{c3::a}} {{c1::b}} {{c3::c}} {{c2::d::hint}} {{c4::e"),
+            vec![1, 2, 3]
+        );
+        assert!(numbers("No deletions here").is_empty());
+    }
+
+    #[test]
+    fn answers_of_one_number() {
+        let text = "{{c1::Paris}} and {{c2::Rome::city}} and {{c1::Madrid::hint}}";
+        assert_eq!(answers(text, 1), vec!["Paris", "Madrid"]);
+        assert_eq!(answers(text, 2), vec!["Rome"]);
+        assert!(answers(text, 3).is_empty());
+    }
+
+    #[test]
+    fn renders_the_active_deletion_hidden() {
+        let text = "{{c1::Paris}} is in {{c2::France::country}}";
+        assert_eq!(
+            texts(render(text, 1, false, Style::default())),
+            vec!["[...]", " is in ", "France"]
+        );
+        assert_eq!(
+            texts(render(text, 2, false, Style::default())),
+            vec!["Paris", " is in ", "[country]"]
+        );
+        let revealed = render(text, 2, true, Style::default());
+        assert_eq!(revealed[2].content, "France");
+        assert_eq!(revealed[2].style.fg, Some(Color::Cyan));
+        assert_eq!(revealed[0].style, Style::default());
+    }
+}
@@ -0,0 +1,35 @@
+use std::error::Error;
+
+use crate::{
+    deck::Deck,
+    scheduler::{Fsrs, Review},
+    screen::ScreenOptions,
+    settings::SETTINGS_FILE,
+};
+
+//Fits the FSRS weights of a deck to its review history and saves them in the deck settings.
+pub fn optimize(deck_name: &str) -> Result<(), Box<dyn Error>> {
+    let options = ScreenOptions::load()?;
+    let deck_path = options.local_directory.join(deck_name);
+    let deck = Deck::read_from_dir(&deck_path)?;
+    let histories: Vec<Vec<Review>> = deck
+        .contents
+        .iter()
+        .flat_map(|card| card.schedules())
+        .map(|schedule| schedule.reviews.clone())
+        .collect();
+    let fsrs = Fsrs::new(
+        deck.settings.fsrs_weights.clone(),
+        deck.settings.desired_retention,
+    );
+    let weights = fsrs.optimize(&histories)?;
+    let mut settings = deck.settings.clone();
+    settings.fsrs_weights = weights;
+    settings.write_to_dir(&deck_path)?;
+    println!(
+        "Saved optimized FSRS weights for '{}' to {}.",
+        deck.deck_title,
+        deck_path.join(SETTINGS_FILE).display()
+    );
+    Ok(())
+}
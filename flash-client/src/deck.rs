@@ -1,18 +1,53 @@
 use std::{
+    collections::{BTreeMap, HashMap},
     error::Error,
-    fs::read_dir,
+    fs::{self, read_dir},
     path::{Path, PathBuf},
 };
 
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use rand::{seq::SliceRandom, thread_rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 use tui::widgets::Widget;
 
-use crate::card::Card;
+use crate::{
+    card::{Card, Variant},
+    filter::Filter,
+    scheduler::{CardState, Grade, Schedule},
+    settings::{CardOrder, DeckSettings, SchedulerKind},
+    util,
+};
+
+//The manual card order, saved as a list of card ids.
+pub const ORDER_FILE: &str = "order.json";
+const CODE_SCROLL_STEP: usize = 4;
+
+//One thing to study, a card asked as one of its variants.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Item {
+    pub card: usize,
+    pub variant: Variant,
+}
+
+//A directory cards were loaded from, a merged deck keeps one for each deck it was made of.
+#[derive(Clone, Debug, Default)]
+pub struct DeckSource {
+    pub title: String,
+    pub path: PathBuf,
+    pub settings: DeckSettings,
+}
 
 #[derive(Clone, Debug, Default)]
 pub struct Deck {
     pub deck_title: String,
     pub contents: Box<[Card]>,
     pub cur_card: usize,
+    pub cur_variant: Variant,
+    pub settings: DeckSettings,
+    pub sources: Vec<DeckSource>,
+    //How many columns the code on screen is scrolled sideways.
+    pub code_scroll: usize,
 }
 
 impl Deck {
@@ -21,6 +56,10 @@ impl Deck {
             deck_title: title.to_string(),
             contents: cards.into_boxed_slice(),
             cur_card: 0,
+            cur_variant: Variant::Forward,
+            settings: DeckSettings::default(),
+            sources: Vec::new(),
+            code_scroll: 0,
         };
     }
 
@@ -34,7 +73,7 @@ impl Deck {
                             && entry
                                 .file_name()
                                 .into_string()
-                                .and_then(|x| Ok(x.contains(".json")))
+                                .and_then(|x| Ok(x.contains(".json") && x != ORDER_FILE))
                                 .unwrap_or(false)
                         {
                             files.push(entry.file_name().into_string().unwrap());
@@ -46,39 +85,360 @@ impl Deck {
                 .iter()
                 .filter_map(|file_name| {
                     let card_option = match Card::read_from_file(&dirpath.join(file_name)) {
-                        Ok(inner) => Some(inner),
+                        Ok(mut inner) => {
+                            //Cards from before creation times were saved fall back to the file time.
+                            if inner.created.is_none() {
+                                inner.created = fs::metadata(dirpath.join(file_name))
+                                    .and_then(|metadata| metadata.modified())
+                                    .ok()
+                                    .map(DateTime::<Utc>::from);
+                            }
+                            //Cards saved under their title are moved over to their id, keeping that time.
+                            if *file_name != inner.saved_name()
+                                && !dirpath.join(inner.saved_name()).exists()
+                                && inner.clone().write_to_file(dirpath.to_path_buf()).is_ok()
+                            {
+                                let _ = fs::remove_file(dirpath.join(file_name));
+                            }
+                            Some(inner)
+                        }
                         Err(_) => None,
                     };
                     return card_option;
                 })
                 .collect();
-            return Ok(Deck::new(
+            let mut deck = Deck::new(
                 dirpath
                     .file_stem()
                     .and_then(|dir_name| dir_name.to_str())
                     .unwrap_or("Unnamed"),
-                cards,
-            ));
+                Deck::sort_by_saved_order(dirpath, cards),
+            );
+            deck.settings = DeckSettings::read_from_dir(dirpath);
+            for card in deck.contents.iter_mut() {
+                card.note = deck.settings.note_type.clone();
+            }
+            deck.sources = vec![DeckSource {
+                title: deck.deck_title.clone(),
+                path: dirpath.to_path_buf(),
+                settings: deck.settings.clone(),
+            }];
+            return Ok(deck);
         }
         return Err(String::from("Failed to read directory."));
     }
 
-    pub fn write_to_dir(self, mut parent_path: PathBuf) -> Result<(), Box<dyn Error>> {
-        parent_path.push(self.deck_title);
-        for card in self.contents.into_vec().into_iter() {
+    //A deck together with every deck in the folders below it, so a parent folder studies as one deck.
+    pub fn read_tree(dirpath: &Path) -> Result<Self, String> {
+        if let Some(filter) = Filter::read_from_dir(dirpath) {
+            return Ok(Deck::read_filtered(dirpath, &filter, Utc::now()));
+        }
+        let deck = Deck::read_from_dir(dirpath)?;
+        let mut sub_directories = util::get_sub_directories(dirpath).unwrap_or_default();
+        if sub_directories.is_empty() {
+            return Ok(deck);
+        }
+        sub_directories.sort();
+        let title = deck.deck_title.clone();
+        let mut decks = vec![deck];
+        for sub_directory in sub_directories {
+            if Filter::is_filter(&dirpath.join(&sub_directory)) {
+                continue;
+            }
+            if let Ok(sub_deck) = Deck::read_tree(&dirpath.join(sub_directory)) {
+                decks.push(sub_deck);
+            }
+        }
+        Ok(Deck::merge(&title, decks))
+    }
+
+    //The cards of every deck in the same folder as a filtered deck that match its query.
+    pub fn read_filtered(dirpath: &Path, filter: &Filter, now: DateTime<Utc>) -> Self {
+        let title = dirpath
+            .file_name()
+            .and_then(|dir_name| dir_name.to_str())
+            .unwrap_or("Unnamed");
+        let mut deck = match dirpath.parent() {
+            Some(parent) => Deck::read_matching(parent, title, filter, now),
+            None => Deck::new(title, Vec::new()),
+        };
+        deck.settings = DeckSettings::read_from_dir(dirpath);
+        deck
+    }
+
+    //Every card of every deck in a folder that matches a filter, like all the cards with a tag.
+    //Cards stay in their own decks, so they are scheduled and saved there.
+    pub fn read_matching(root: &Path, title: &str, filter: &Filter, now: DateTime<Utc>) -> Self {
+        let mut sub_directories = util::get_sub_directories(root).unwrap_or_default();
+        sub_directories.sort();
+        let decks = sub_directories
+            .iter()
+            .map(|name| root.join(name))
+            .filter(|path| !Filter::is_filter(path))
+            .filter_map(|path| Deck::read_tree(&path).ok())
+            .collect();
+        let mut deck = Deck::merge(title, decks);
+        let cards: Vec<Card> = (0..deck.len())
+            .filter(|index| {
+                let deck_title = deck
+                    .source(*index)
+                    .map(|source| source.title.as_str())
+                    .unwrap_or_default();
+                filter.matches(&deck.contents[*index], deck_title, now)
+            })
+            .map(|index| deck.contents[index].clone())
+            .collect();
+        deck.contents = cards.into_boxed_slice();
+        deck
+    }
+
+    //One deck made of several, the first deck's settings decide how the merged session is run.
+    pub fn merge(title: &str, decks: Vec<Deck>) -> Self {
+        let mut merged = Deck::new(title, Vec::new());
+        merged.settings = decks
+            .first()
+            .map(|deck| deck.settings.clone())
+            .unwrap_or_default();
+        let mut cards = Vec::new();
+        for deck in decks {
+            let offset = merged.sources.len();
+            merged.sources.extend(deck.sources);
+            cards.extend(deck.contents.into_vec().into_iter().map(|card| Card {
+                source: card.source + offset,
+                ..card
+            }));
+        }
+        merged.contents = cards.into_boxed_slice();
+        merged
+    }
+
+    pub fn source(&self, card: usize) -> Option<&DeckSource> {
+        self.sources.get(self.contents.get(card)?.source)
+    }
+
+    //Cards are scheduled with the settings of the deck they came from.
+    pub fn card_settings(&self, card: usize) -> &DeckSettings {
+        self.source(card)
+            .map(|source| &source.settings)
+            .unwrap_or(&self.settings)
+    }
+
+    pub fn is_merged(&self) -> bool {
+        self.sources.len() > 1
+    }
+
+    //The folder a card is saved in, cards of merged decks go back to the deck they came from.
+    pub fn card_directory(&self, card: usize, parent_path: &Path) -> PathBuf {
+        self.source(card)
+            .map(|source| source.path.clone())
+            .unwrap_or_else(|| parent_path.join(&self.deck_title))
+    }
+
+    //Tags used across the deck with how many cards have each, in alphabetical order.
+    pub fn tag_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for tag in self.contents.iter().flat_map(|card| card.tags.iter()) {
+            *counts.entry(tag.clone()).or_default() += 1;
+        }
+        counts.into_iter().collect()
+    }
+
+    pub fn write_to_dir(self, parent_path: PathBuf) -> Result<(), Box<dyn Error>> {
+        let mut directories: Vec<PathBuf> = Vec::new();
+        for index in 0..self.contents.len() {
             let new_card = Card {
-                title: card.title,
-                sections: card.sections,
                 current_section: 0,
+                ..self.contents[index].clone()
             };
-            new_card.write_to_file(parent_path.to_path_buf())?;
+            let directory = self.card_directory(index, &parent_path);
+            new_card.write_to_file(directory.clone())?;
+            if !directories.contains(&directory) {
+                directories.push(directory);
+            }
         }
+        //Every folder the cards came from keeps its own manual order.
+        for directory in directories {
+            let cards: Vec<&Card> = (0..self.contents.len())
+                .filter(|index| self.card_directory(*index, &parent_path) == directory)
+                .map(|index| &self.contents[index])
+                .collect();
+            Deck::write_order(&directory, &cards)?;
+        }
+        Ok(())
+    }
+
+    fn saved_order(dirpath: &Path) -> Vec<String> {
+        fs::read_to_string(dirpath.join(ORDER_FILE))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    //The given cards take the places of their old entries, so cards of the folder left out of a
+    //merged or tag session keep theirs. Entries of cards that are gone are dropped.
+    fn write_order(dirpath: &Path, cards: &[&Card]) -> Result<(), Box<dyn Error>> {
+        let keys: Vec<String> = cards
+            .iter()
+            .flat_map(|card| [card.id.to_string(), card.title.clone()])
+            .collect();
+        let mut ids = cards.iter().map(|card| card.id.to_string());
+        let mut order: Vec<String> = Deck::saved_order(dirpath)
+            .into_iter()
+            .filter_map(|key| {
+                if keys.contains(&key) {
+                    ids.next()
+                } else {
+                    dirpath
+                        .join(format!("{}.json", key))
+                        .is_file()
+                        .then_some(key)
+                }
+            })
+            .collect();
+        order.extend(ids);
+        fs::write(dirpath.join(ORDER_FILE), serde_json::to_string(&order)?)?;
         Ok(())
     }
 
+    //Puts cards in the order saved with the deck, cards missing from it follow by creation time and title.
+    //Orders saved before cards had ids list titles instead.
+    fn sort_by_saved_order(dirpath: &Path, mut cards: Vec<Card>) -> Vec<Card> {
+        let saved = Deck::saved_order(dirpath);
+        let positions: HashMap<&String, usize> = saved
+            .iter()
+            .enumerate()
+            .map(|(position, key)| (key, position))
+            .collect();
+        let position = |card: &Card| {
+            positions
+                .get(&card.id.to_string())
+                .or_else(|| positions.get(&card.title))
+                .copied()
+                .unwrap_or(usize::MAX)
+        };
+        cards.sort_by(|a, b| {
+            let a_position = position(a);
+            let b_position = position(b);
+            a_position
+                .cmp(&b_position)
+                .then(a.created.cmp(&b.created))
+                .then(a.title.cmp(&b.title))
+        });
+        cards
+    }
+
+    //Indices into contents in the order the deck settings ask for.
+    pub fn study_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.contents.len()).collect();
+        match self.settings.order {
+            CardOrder::Manual => (),
+            CardOrder::Alphabetical => {
+                order.sort_by(|a, b| self.contents[*a].title.cmp(&self.contents[*b].title))
+            }
+            CardOrder::Created => order.sort_by(|a, b| {
+                self.contents[*a]
+                    .created
+                    .cmp(&self.contents[*b].created)
+                    .then(self.contents[*a].title.cmp(&self.contents[*b].title))
+            }),
+            CardOrder::Random => return self.shuffled_order(),
+        }
+        order
+    }
+
+    //Shuffles from the manual order, so the same seed gives everyone sharing the deck the same sequence.
+    pub fn shuffled_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.contents.len()).collect();
+        match self.settings.seed {
+            Some(seed) => order.shuffle(&mut ChaCha8Rng::seed_from_u64(seed)),
+            None => order.shuffle(&mut thread_rng()),
+        }
+        order
+    }
+
+    //Every variant of the given cards, a card's variants staying next to each other.
+    pub fn items(&self, order: Vec<usize>) -> Vec<Item> {
+        order
+            .into_iter()
+            .flat_map(|card| {
+                self.contents[card]
+                    .variants(self.card_settings(card).bidirectional)
+                    .into_iter()
+                    .map(move |variant| Item { card, variant })
+            })
+            .collect()
+    }
+
+    pub fn schedule(&self, item: Item) -> &Schedule {
+        self.contents[item.card].schedule(item.variant)
+    }
+
+    //A card title, with the variant it is asked as when that isn't the usual one.
+    pub fn item_title(&self, item: Item) -> String {
+        let card = &self.contents[item.card];
+        let title = card.title.clone();
+        match card.variant_label(item.variant) {
+            Some(label) => format!("{} ({})", title, label),
+            None => title,
+        }
+    }
+
+    pub fn current_item(&self) -> Item {
+        Item {
+            card: self.cur_card,
+            variant: self.cur_variant,
+        }
+    }
+
+    //Puts an item on screen from its first section.
+    pub fn show(&mut self, item: Item) {
+        self.cur_card = item.card;
+        self.cur_variant = item.variant;
+        self.contents[item.card].current_section = 0;
+        self.code_scroll = 0;
+    }
+
+    //Scrolls code blocks a few columns at a time, no further than the widest line needs.
+    pub fn scroll_code(&mut self, right: bool, width: u16) {
+        let overflow = self
+            .contents
+            .get(self.cur_card)
+            .map(|card| card.code_overflow(self.cur_variant, width))
+            .unwrap_or(0);
+        self.code_scroll = if right {
+            (self.code_scroll + CODE_SCROLL_STEP).min(overflow)
+        } else {
+            self.code_scroll
+                .saturating_sub(CODE_SCROLL_STEP)
+                .min(overflow)
+        };
+    }
+
+    pub fn is_revealed(&self) -> bool {
+        self.contents
+            .get(self.cur_card)
+            .is_none_or(|card| card.is_revealed(self.cur_variant))
+    }
+
+    pub fn reveal(&mut self) {
+        let variant = self.cur_variant;
+        if let Some(card) = self.contents.get_mut(self.cur_card) {
+            card.reveal(variant);
+        }
+    }
+
+    pub fn move_card(&mut self, index: usize, up: bool) -> Option<usize> {
+        let target = if up { index.checked_sub(1)? } else { index + 1 };
+        if target >= self.contents.len() {
+            return None;
+        }
+        self.contents.swap(index, target);
+        Some(target)
+    }
+
     pub fn increment_deck(&mut self, change_card: bool) -> Option<usize> {
         if !self.contents.is_empty() {
-            if let None = self.contents[self.cur_card].increment_section() {
+            if let None = self.contents[self.cur_card].increment_section(self.cur_variant) {
                 if change_card {
                     if let Some(i) = self.cur_card.checked_add(1) {
                         if i >= self.contents.len() {
@@ -103,7 +463,7 @@ impl Deck {
 
     pub fn decrement_deck(&mut self, change_card: bool) -> Option<usize> {
         if !self.contents.is_empty() {
-            if let None = self.contents[self.cur_card].decrement_section() {
+            if let None = self.contents[self.cur_card].decrement_section(self.cur_variant) {
                 if change_card {
                     if let Some(i) = self.cur_card.checked_sub(1) {
                         if i >= self.contents.len() {
@@ -128,11 +488,126 @@ impl Deck {
     pub fn len(&self) -> usize {
         return self.contents.len();
     }
-    pub fn as_widget(&self) -> impl Widget {
+
+    //Due items that still fit in today's new card and review limits, learning items are never held back.
+    //Every source deck of a merged deck keeps to its own limits.
+    pub fn available_items(&self, now: DateTime<Utc>) -> Vec<Item> {
+        let today = now.with_timezone(&Local).date_naive();
+        let mut studied_today: HashMap<usize, (usize, usize)> = HashMap::new();
+        for card in self.contents.iter() {
+            let (new_today, reviews_today) = studied_today.entry(card.source).or_default();
+            for schedule in card.schedules() {
+                let days: Vec<NaiveDate> = schedule
+                    .reviews
+                    .iter()
+                    .map(|review| review.time.with_timezone(&Local).date_naive())
+                    .collect();
+                for (index, day) in days.iter().enumerate() {
+                    if *day != today {
+                        continue;
+                    }
+                    //Repeats from learning steps on the same day do not use up the review limit.
+                    if index == 0 {
+                        *new_today += 1;
+                    } else if days[index - 1] != today {
+                        *reviews_today += 1;
+                    }
+                }
+            }
+        }
+        let mut left: HashMap<usize, (usize, usize)> = studied_today
+            .into_iter()
+            .map(|(source, (new_today, reviews_today))| {
+                let settings = self
+                    .sources
+                    .get(source)
+                    .map(|source| &source.settings)
+                    .unwrap_or(&self.settings);
+                (
+                    source,
+                    (
+                        settings.new_cards_per_day.saturating_sub(new_today),
+                        settings.reviews_per_day.saturating_sub(reviews_today),
+                    ),
+                )
+            })
+            .collect();
+        let order = self.items(self.study_order());
+        let mut due: Vec<usize> = (0..order.len())
+            .filter(|position| {
+                let item = order[*position];
+                !self.contents[item.card].suspended && self.schedule(item).is_due(now)
+            })
+            .collect();
+        //Most overdue reviews first so they are the ones kept when the limit is hit.
+        due.sort_by_key(|position| self.schedule(order[*position]).due);
+        let mut available = Vec::new();
+        for position in due {
+            let item = order[position];
+            let (new_left, reviews_left) = left.entry(self.contents[item.card].source).or_default();
+            match self.schedule(item).state() {
+                CardState::New if *new_left > 0 => {
+                    *new_left -= 1;
+                    available.push(position);
+                }
+                CardState::Learning => available.push(position),
+                CardState::Review if *reviews_left > 0 => {
+                    *reviews_left -= 1;
+                    available.push(position);
+                }
+                _ => (),
+            }
+        }
+        //Hand the items back in study order, which is also the order new cards get introduced in.
+        available.sort();
+        available
+            .into_iter()
+            .map(|position| order[position])
+            .collect()
+    }
+
+    //How many cards sit in each Leitner box, box 1 first.
+    //Each card counts by the boxes of its own deck, cards of decks on another scheduler are left out.
+    pub fn box_counts(&self) -> Vec<usize> {
+        let mut counts: Vec<usize> = Vec::new();
+        for item in self.items((0..self.contents.len()).collect()) {
+            let settings = self.card_settings(item.card);
+            let boxes = settings.leitner_intervals.len();
+            if settings.scheduler != SchedulerKind::Leitner || boxes == 0 {
+                continue;
+            }
+            if counts.len() < boxes {
+                counts.resize(boxes, 0);
+            }
+            counts[self.schedule(item).leitner_box.clamp(1, boxes) - 1] += 1;
+        }
+        counts
+    }
+
+    pub fn grade_current(&mut self, grade: Grade, now: DateTime<Utc>) -> Option<&Card> {
+        let settings = self.card_settings(self.cur_card).clone();
+        let scheduler = settings.scheduler();
+        let steps = settings.steps();
+        let card = self.contents.get_mut(self.cur_card)?;
+        let schedule = card.schedule_mut(self.cur_variant);
+        steps.review(scheduler.as_ref(), schedule, grade, now);
+        let lapses = schedule.lapses;
+        card.current_section = 0;
+        if !card.leech && lapses >= settings.leech_threshold {
+            card.leech = true;
+            card.suspended = settings.suspend_leeches;
+        }
+        Some(card)
+    }
+    pub fn as_widget(&self, width: u16) -> impl Widget {
         if !self.contents.is_empty() {
-            return self.contents[self.cur_card].as_widget();
+            return self.contents[self.cur_card].as_widget(
+                self.cur_variant,
+                width,
+                self.code_scroll,
+            );
         } else {
-            return Card::default().as_widget();
+            return Card::default().as_widget(Variant::Forward, width, 0);
         }
     }
 
@@ -146,3 +621,263 @@ impl Deck {
         names
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        scheduler::Review,
+        test_util::{card, deck, item, start, temp_dir},
+    };
+
+    fn source(scheduler: SchedulerKind, leitner_intervals: Vec<u32>) -> DeckSource {
+        DeckSource {
+            settings: DeckSettings {
+                scheduler,
+                leitner_intervals,
+                ..DeckSettings::default()
+            },
+            ..DeckSource::default()
+        }
+    }
+
+    #[test]
+    fn box_counts_follow_each_source() {
+        let mut deck = deck();
+        deck.sources = vec![
+            source(SchedulerKind::Leitner, vec![1, 3]),
+            source(SchedulerKind::Sm2, vec![1, 2, 4, 8, 16]),
+            source(SchedulerKind::Leitner, vec![1, 2, 4]),
+        ];
+        for (card, (source, leitner_box)) in
+            deck.contents
+                .iter_mut()
+                .zip([(0, 5), (1, 4), (2, 3), (2, 1)])
+        {
+            card.source = source;
+            card.schedule.leitner_box = leitner_box;
+        }
+        assert_eq!(deck.box_counts(), vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn box_counts_skip_other_schedulers_and_empty_boxes() {
+        let mut deck = deck();
+        assert!(deck.box_counts().is_empty());
+        deck.settings.scheduler = SchedulerKind::Leitner;
+        deck.settings.leitner_intervals = Vec::new();
+        assert!(deck.box_counts().is_empty());
+        deck.settings.leitner_intervals = vec![1, 2];
+        assert_eq!(deck.box_counts(), vec![4, 0]);
+    }
+
+    fn titles(deck: &Deck) -> Vec<&str> {
+        deck.contents
+            .iter()
+            .map(|card| card.title.as_str())
+            .collect()
+    }
+
+    //Cards saved before ids were added, named after their title and without an id inside.
+    fn write_legacy(dirpath: &Path, file_name: &str, title: &str) {
+        fs::write(
+            dirpath.join(file_name),
+            format!(
+                r#"{{"title":"{}","sections":["{}","Paris"],"current_section":0}}"#,
+                title, title
+            ),
+        )
+        .unwrap();
+    }
+
+    fn json_files(dirpath: &Path) -> Vec<String> {
+        let mut names: Vec<String> = read_dir(dirpath)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.ends_with(".json") && name != ORDER_FILE)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn legacy_cards_move_to_their_id() {
+        let dirpath = temp_dir();
+        write_legacy(&dirpath, "France.json", "France");
+        let deck = Deck::read_from_dir(&dirpath).unwrap();
+        assert_eq!(titles(&deck), vec!["France"]);
+        let id = deck.contents[0].id;
+        assert_eq!(json_files(&dirpath), vec![format!("{}.json", id)]);
+        //Read again the card keeps the id it was given.
+        let deck = Deck::read_from_dir(&dirpath).unwrap();
+        assert_eq!(deck.contents[0].id, id);
+        assert!(deck.contents[0].created.is_some());
+        fs::remove_dir_all(dirpath).unwrap();
+    }
+
+    #[test]
+    fn legacy_cards_with_the_same_title_both_move() {
+        let dirpath = temp_dir();
+        write_legacy(&dirpath, "France.json", "France");
+        write_legacy(&dirpath, "France copy.json", "France");
+        let deck = Deck::read_from_dir(&dirpath).unwrap();
+        assert_eq!(titles(&deck), vec!["France", "France"]);
+        assert_ne!(deck.contents[0].id, deck.contents[1].id);
+        let mut expected: Vec<String> =
+            deck.contents.iter().map(|card| card.saved_name()).collect();
+        expected.sort();
+        assert_eq!(json_files(&dirpath), expected);
+        fs::remove_dir_all(dirpath).unwrap();
+    }
+
+    #[test]
+    fn saved_order_skips_unknown_ids_and_appends_missing_cards() {
+        let dirpath = temp_dir();
+        let cards = deck().contents.into_vec();
+        for card in cards.iter() {
+            card.clone().write_to_file(dirpath.clone()).unwrap();
+        }
+        let order = [
+            cards[2].id.to_string(),
+            Uuid::new_v4().to_string(),
+            String::from("Peru"),
+            cards[0].id.to_string(),
+        ];
+        fs::write(
+            dirpath.join(ORDER_FILE),
+            serde_json::to_string(&order).unwrap(),
+        )
+        .unwrap();
+        let deck = Deck::read_from_dir(&dirpath).unwrap();
+        assert_eq!(titles(&deck), vec!["Italy", "Peru", "France", "Spain"]);
+        fs::remove_dir_all(dirpath).unwrap();
+    }
+
+    #[test]
+    fn merged_decks_save_the_order_of_each_folder() {
+        let root = temp_dir();
+        for (name, titles) in [
+            ("Europe", ["France", "Spain"]),
+            ("America", ["Peru", "Chile"]),
+        ] {
+            fs::create_dir(root.join(name)).unwrap();
+            for title in titles {
+                card(title, "").write_to_file(root.join(name)).unwrap();
+            }
+        }
+        let decks: Vec<Deck> = ["Europe", "America"]
+            .iter()
+            .map(|name| Deck::read_from_dir(&root.join(name)).unwrap())
+            .collect();
+        let expected: Vec<Vec<String>> = decks
+            .iter()
+            .map(|deck| titles(deck).into_iter().rev().map(String::from).collect())
+            .collect();
+        let mut merged = Deck::merge("World", decks);
+        merged.contents.reverse();
+        merged.write_to_dir(root.clone()).unwrap();
+        for (name, expected) in ["Europe", "America"].iter().zip(expected) {
+            assert_eq!(
+                titles(&Deck::read_from_dir(&root.join(name)).unwrap()),
+                expected
+            );
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn partial_decks_leave_other_cards_in_place() {
+        let dirpath = temp_dir();
+        for card in deck().contents.iter() {
+            card.clone().write_to_file(dirpath.clone()).unwrap();
+        }
+        let full = Deck::read_from_dir(&dirpath).unwrap();
+        full.clone()
+            .write_to_dir(dirpath.parent().unwrap().to_path_buf())
+            .unwrap();
+        //Like a tag session holding only the first and last card, swapped around.
+        let mut partial = full.clone();
+        partial.contents =
+            vec![full.contents[3].clone(), full.contents[0].clone()].into_boxed_slice();
+        partial.write_to_dir(PathBuf::new()).unwrap();
+        let after = Deck::read_from_dir(&dirpath).unwrap();
+        let mut expected = titles(&full);
+        expected.swap(0, 3);
+        assert_eq!(titles(&after), expected);
+        fs::remove_dir_all(dirpath).unwrap();
+    }
+
+    //A card that was last seen the given number of days ago and is due again today.
+    fn due_review(card: &mut Card, days_ago: i64) {
+        card.schedule.reviews.push(Review {
+            time: start() - chrono::Duration::days(days_ago + 1),
+            grade: Grade::Good,
+        });
+        card.schedule.interval = 1;
+        card.schedule.due = Some(start() - chrono::Duration::days(days_ago));
+    }
+
+    #[test]
+    fn new_cards_stop_at_the_daily_limit() {
+        let mut deck = deck();
+        deck.settings.new_cards_per_day = 2;
+        assert_eq!(deck.available_items(start()), vec![item(0), item(1)]);
+        //A card first studied today uses up one of them.
+        deck.contents[0].schedule.reviews.push(Review {
+            time: start(),
+            grade: Grade::Good,
+        });
+        deck.contents[0].schedule.due = Some(start() + chrono::Duration::days(1));
+        assert_eq!(deck.available_items(start()), vec![item(1)]);
+    }
+
+    #[test]
+    fn most_overdue_reviews_are_kept_at_the_limit() {
+        let mut deck = deck();
+        deck.settings.new_cards_per_day = 0;
+        deck.settings.reviews_per_day = 2;
+        for (card, days_ago) in deck.contents.iter_mut().zip([1, 5, 0, 3]) {
+            due_review(card, days_ago);
+        }
+        assert_eq!(deck.available_items(start()), vec![item(1), item(3)]);
+        //Learning cards are never held back.
+        deck.contents[2].schedule.learning_step = Some(0);
+        assert_eq!(
+            deck.available_items(start()),
+            vec![item(1), item(2), item(3)]
+        );
+        deck.contents[1].suspended = true;
+        assert_eq!(
+            deck.available_items(start()),
+            vec![item(0), item(2), item(3)]
+        );
+    }
+
+    #[test]
+    fn merged_decks_keep_their_own_limits() {
+        let mut deck = deck();
+        deck.settings.new_cards_per_day = 1;
+        deck.sources = vec![
+            DeckSource {
+                settings: deck.settings.clone(),
+                ..DeckSource::default()
+            },
+            DeckSource {
+                settings: DeckSettings {
+                    new_cards_per_day: 2,
+                    ..DeckSettings::default()
+                },
+                ..DeckSource::default()
+            },
+        ];
+        for (card, source) in deck.contents.iter_mut().zip([0, 0, 1, 1]) {
+            card.source = source;
+        }
+        assert_eq!(
+            deck.available_items(start()),
+            vec![item(0), item(2), item(3)]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::Variant,
+    deck::{Deck, Item},
+    quiz::Question,
+    util,
+};
+
+pub const EXAMS_DIRECTORY: &str = "exams";
+
+//A fixed set of multiple choice questions against the clock, nothing is marked until it is handed in.
+#[derive(Clone, Debug, Default)]
+pub struct Exam {
+    pub questions: Vec<(Item, Question)>,
+    pub current: usize,
+    pub started: DateTime<Utc>,
+    pub time_limit: Duration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExamAnswer {
+    pub card: String,
+    pub variant: Variant,
+    pub answer: String,
+    pub chosen: Option<String>,
+    pub correct: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExamReport {
+    pub deck: String,
+    pub started: DateTime<Utc>,
+    pub finished: DateTime<Utc>,
+    pub time_limit_seconds: i64,
+    pub score: usize,
+    pub total: usize,
+    pub answers: Vec<ExamAnswer>,
+}
+
+impl Exam {
+    //Questions are drawn in random order from every item that can be asked as multiple choice.
+    pub fn new(deck: &Deck, now: DateTime<Utc>) -> Self {
+        let questions = deck
+            .items(deck.shuffled_order())
+            .into_iter()
+            .filter(|item| !deck.contents[item.card].suspended)
+            .filter_map(|item| Question::new(deck, item).map(|question| (item, question)))
+            .take(deck.settings.exam_questions)
+            .collect();
+        Exam {
+            questions,
+            current: 0,
+            started: now,
+            time_limit: deck.settings.exam_time_limit,
+        }
+    }
+
+    pub fn remaining(&self, now: DateTime<Utc>) -> Duration {
+        (self.started + self.time_limit - now).max(Duration::zero())
+    }
+
+    pub fn is_over(&self, now: DateTime<Utc>) -> bool {
+        self.remaining(now).is_zero()
+    }
+
+    pub fn current_item(&self) -> Option<Item> {
+        self.questions.get(self.current).map(|(item, _)| *item)
+    }
+
+    pub fn current_question(&self) -> Option<&Question> {
+        self.questions
+            .get(self.current)
+            .map(|(_, question)| question)
+    }
+
+    //Records a choice and moves on to the next question still without an answer.
+    pub fn answer(&mut self, choice: usize) {
+        if let Some((_, question)) = self.questions.get_mut(self.current) {
+            if choice < question.choices.len() {
+                question.chosen = Some(choice);
+            }
+        }
+        let count = self.questions.len();
+        if let Some(next) = (1..=count)
+            .map(|offset| (self.current + offset) % count)
+            .find(|index| !self.questions[*index].1.is_answered())
+        {
+            self.current = next;
+        }
+    }
+
+    pub fn move_to(&mut self, forward: bool) {
+        if forward && self.current + 1 < self.questions.len() {
+            self.current += 1;
+        } else if !forward && self.current > 0 {
+            self.current -= 1;
+        }
+    }
+
+    pub fn answered(&self) -> usize {
+        self.questions
+            .iter()
+            .filter(|(_, question)| question.is_answered())
+            .count()
+    }
+
+    pub fn report(&self, deck: &Deck, now: DateTime<Utc>) -> ExamReport {
+        let answers: Vec<ExamAnswer> = self
+            .questions
+            .iter()
+            .map(|(item, question)| ExamAnswer {
+                card: deck.contents[item.card].title.clone(),
+                variant: item.variant,
+                answer: question.choices[question.correct].clone(),
+                chosen: question
+                    .chosen
+                    .map(|chosen| question.choices[chosen].clone()),
+                correct: question.is_correct(),
+            })
+            .collect();
+        ExamReport {
+            deck: deck.deck_title.clone(),
+            started: self.started,
+            finished: now.min(self.started + self.time_limit),
+            time_limit_seconds: self.time_limit.num_seconds(),
+            score: answers.iter().filter(|answer| answer.correct).count(),
+            total: answers.len(),
+            answers,
+        }
+    }
+}
+
+impl ExamReport {
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.score as f64 * 100.0 / self.total as f64
+    }
+
+    //Reports are kept as one JSON file per exam, named after the deck and the time it was taken.
+    pub fn write_to_dir(&self, config_directory: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let directory = config_directory.join(EXAMS_DIRECTORY);
+        fs::create_dir_all(&directory)?;
+        let file_path = directory.join(format!(
+            "{} {}.json",
+            util::file_name(&self.deck),
+            self.started
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H-%M-%S")
+        ));
+        fs::write(&file_path, serde_json::to_string_pretty(self)?)?;
+        Ok(file_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{card::Card, quiz::QUIZ_CHOICES, test_util::start};
+
+    //The shared deck with a card that can't be asked added at the end.
+    fn deck() -> Deck {
+        let mut cards = crate::test_util::deck().contents.into_vec();
+        cards.push(Card::new(String::from("No answer")));
+        let mut deck = Deck::new("Capitals", cards);
+        deck.settings.exam_questions = 3;
+        deck.settings.exam_time_limit = Duration::minutes(10);
+        deck
+    }
+
+    #[test]
+    fn questions_are_drawn_from_answerable_cards() {
+        let mut deck = deck();
+        deck.contents[0].suspended = true;
+        let exam = Exam::new(&deck, start());
+        assert_eq!(exam.questions.len(), 3);
+        assert!(exam.questions.iter().all(|(item, question)| item.card != 0
+            && item.card != 4
+            && question.choices.len() == QUIZ_CHOICES
+            && question.chosen.is_none()));
+    }
+
+    #[test]
+    fn countdown_runs_out() {
+        let exam = Exam::new(&deck(), start());
+        assert_eq!(exam.remaining(start()), Duration::minutes(10));
+        assert_eq!(
+            exam.remaining(start() + Duration::minutes(4)),
+            Duration::minutes(6)
+        );
+        assert!(!exam.is_over(start() + Duration::minutes(9)));
+        assert!(exam.is_over(start() + Duration::minutes(10)));
+        assert_eq!(
+            exam.remaining(start() + Duration::minutes(12)),
+            Duration::zero()
+        );
+    }
+
+    #[test]
+    fn answering_moves_to_the_next_unanswered_question() {
+        let mut exam = Exam::new(&deck(), start());
+        exam.move_to(true);
+        exam.answer(0);
+        assert_eq!(exam.current, 2);
+        exam.answer(0);
+        assert_eq!(exam.current, 0);
+        //Out of range choices leave the question unanswered.
+        exam.answer(9);
+        assert_eq!(exam.current, 0);
+        assert_eq!(exam.answered(), 2);
+        exam.move_to(false);
+        assert_eq!(exam.current, 0);
+    }
+
+    #[test]
+    fn report_scores_the_answers() {
+        let deck = deck();
+        let mut exam = Exam::new(&deck, start());
+        let correct = exam.questions[0].1.correct;
+        exam.answer(correct);
+        let wrong = (exam.questions[1].1.correct + 1) % exam.questions[1].1.choices.len();
+        exam.answer(wrong);
+        let report = exam.report(&deck, start() + Duration::minutes(15));
+        assert_eq!((report.score, report.total), (1, 3));
+        assert!((report.percentage() - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.finished, start() + Duration::minutes(10));
+        assert_eq!(report.time_limit_seconds, 600);
+        assert!(report.answers[0].correct);
+        assert!(!report.answers[1].correct);
+        assert_eq!(report.answers[2].chosen, None);
+        assert_eq!(
+            report.answers[0].chosen.as_ref(),
+            Some(&report.answers[0].answer)
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use std::{error::Error, path::Path};
+
+use chrono::{DateTime, Duration, Utc};
+use ini::Ini;
+
+use crate::{card::Card, scheduler::Grade};
+
+pub const FILTER_FILE: &str = "filter.ini";
+
+#[derive(Clone, Debug, PartialEq)]
+enum Condition {
+    Deck(String),
+    Tag(String),
+    Text(String),
+    Due,
+    New,
+    Learning,
+    Suspended,
+    Leech,
+    LapsesAbove(u32),
+    LapsesBelow(u32),
+    LapsesEqual(u32),
+    //Reviewed, or answered Again, within the given number of days.
+    Reviewed(i64),
+    Missed(i64),
+}
+
+//A saved search over every deck next to it, studied like a deck without copying any card files.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    pub query: String,
+    //Every condition has to hold, a leading '-' turns a condition around.
+    conditions: Vec<(bool, Condition)>,
+}
+
+impl Filter {
+    pub fn parse(query: &str) -> Self {
+        let conditions = query
+            .split_whitespace()
+            .map(|word| match word.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (false, Filter::parse_condition(rest)),
+                _ => (true, Filter::parse_condition(word)),
+            })
+            .collect();
+        Filter {
+            query: query.trim().to_string(),
+            conditions,
+        }
+    }
+
+    fn parse_condition(word: &str) -> Condition {
+        let lower = word.to_lowercase();
+        let number = |value: &str| value.parse::<u32>().ok();
+        //Day counts too large to make a duration are searched as text like any other bad number.
+        let days = |value: &str| {
+            value
+                .parse::<i64>()
+                .ok()
+                .filter(|days| Duration::try_days(*days).is_some())
+        };
+        if let Some(deck) = lower.strip_prefix("deck:") {
+            return Condition::Deck(deck.to_string());
+        }
+        if let Some(tag) = lower.strip_prefix("tag:") {
+            return Condition::Tag(tag.trim_start_matches('#').to_string());
+        }
+        if let Some(days) = lower.strip_prefix("reviewed:").and_then(days) {
+            return Condition::Reviewed(days);
+        }
+        if let Some(days) = lower.strip_prefix("missed:").and_then(days) {
+            return Condition::Missed(days);
+        }
+        if let Some(lapses) = lower.strip_prefix("lapses>").and_then(number) {
+            return Condition::LapsesAbove(lapses);
+        }
+        if let Some(lapses) = lower.strip_prefix("lapses<").and_then(number) {
+            return Condition::LapsesBelow(lapses);
+        }
+        if let Some(lapses) = lower.strip_prefix("lapses=").and_then(number) {
+            return Condition::LapsesEqual(lapses);
+        }
+        match lower.as_str() {
+            "is:due" => Condition::Due,
+            "is:new" => Condition::New,
+            "is:learning" => Condition::Learning,
+            "is:suspended" => Condition::Suspended,
+            "is:leech" => Condition::Leech,
+            _ => Condition::Text(lower),
+        }
+    }
+
+    pub fn read_from_dir(dirpath: &Path) -> Option<Self> {
+        let config = Ini::load_from_file(dirpath.join(FILTER_FILE)).ok()?;
+        let query = config
+            .section(Some("Filter"))
+            .and_then(|section| section.get("query"))
+            .unwrap_or_default();
+        Some(Filter::parse(query))
+    }
+
+    pub fn write_to_dir(&self, dirpath: &Path) -> Result<(), Box<dyn Error>> {
+        let mut config = Ini::new();
+        config
+            .with_section(Some("Filter"))
+            .set("query", self.query.clone());
+        config.write_to_file(dirpath.join(FILTER_FILE))?;
+        Ok(())
+    }
+
+    pub fn is_filter(dirpath: &Path) -> bool {
+        dirpath.join(FILTER_FILE).is_file()
+    }
+
+    pub fn matches(&self, card: &Card, deck_title: &str, now: DateTime<Utc>) -> bool {
+        self.conditions
+            .iter()
+            .all(|(wanted, condition)| Filter::holds(condition, card, deck_title, now) == *wanted)
+    }
+
+    //Scheduling conditions hold when any direction or cloze of the card meets them.
+    fn holds(condition: &Condition, card: &Card, deck_title: &str, now: DateTime<Utc>) -> bool {
+        let lapses = card
+            .schedules()
+            .map(|schedule| schedule.lapses)
+            .max()
+            .unwrap_or(0);
+        match condition {
+            Condition::Deck(deck) => deck_title.to_lowercase().contains(deck.as_str()),
+            Condition::Tag(tag) => card.tags.iter().any(|card_tag| card_tag == tag),
+            Condition::Text(text) => {
+                card.title.to_lowercase().contains(text.as_str())
+                    || card
+                        .sections
+                        .iter()
+                        .any(|section| section.to_lowercase().contains(text.as_str()))
+            }
+            Condition::Due => card
+                .schedules()
+                .any(|schedule| !schedule.is_new() && schedule.is_due(now)),
+            Condition::New => card.schedules().any(|schedule| schedule.is_new()),
+            Condition::Learning => card
+                .schedules()
+                .any(|schedule| schedule.learning_step.is_some()),
+            Condition::Suspended => card.suspended,
+            Condition::Leech => card.leech,
+            Condition::LapsesAbove(limit) => lapses > *limit,
+            Condition::LapsesBelow(limit) => lapses < *limit,
+            Condition::LapsesEqual(limit) => lapses == *limit,
+            Condition::Reviewed(days) => since(now, *days).is_some_and(|since| {
+                card.schedules()
+                    .any(|schedule| schedule.reviews.iter().any(|review| review.time >= since))
+            }),
+            Condition::Missed(days) => since(now, *days).is_some_and(|since| {
+                card.schedules().any(|schedule| {
+                    schedule
+                        .reviews
+                        .iter()
+                        .any(|review| review.grade == Grade::Again && review.time >= since)
+                })
+            }),
+        }
+    }
+}
+
+//The start of the last given number of days, None when that goes back further than a date can.
+fn since(now: DateTime<Utc>, days: i64) -> Option<DateTime<Utc>> {
+    now.checked_sub_signed(Duration::try_days(days)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scheduler::Review, test_util::start};
+
+    fn card(title: &str) -> Card {
+        crate::test_util::card(title, "The answer")
+    }
+
+    fn reviewed(mut card: Card, days_ago: i64, grade: Grade) -> Card {
+        card.schedule.reviews.push(Review {
+            time: start() - Duration::days(days_ago),
+            grade,
+        });
+        card.schedule.due = Some(start() - Duration::days(days_ago) + Duration::days(1));
+        card
+    }
+
+    #[test]
+    fn parses_every_condition() {
+        let filter = Filter::parse(
+            " deck:Spanish tag:#Verbs -is:new is:due is:learning is:suspended is:leech \
+             lapses>2 lapses<5 lapses=3 reviewed:7 missed:1 Hola -",
+        );
+        assert_eq!(
+            filter.query,
+            "deck:Spanish tag:#Verbs -is:new is:due is:learning is:suspended is:leech \
+             lapses>2 lapses<5 lapses=3 reviewed:7 missed:1 Hola -"
+        );
+        assert_eq!(
+            filter.conditions,
+            vec![
+                (true, Condition::Deck(String::from("spanish"))),
+                (true, Condition::Tag(String::from("verbs"))),
+                (false, Condition::New),
+                (true, Condition::Due),
+                (true, Condition::Learning),
+                (true, Condition::Suspended),
+                (true, Condition::Leech),
+                (true, Condition::LapsesAbove(2)),
+                (true, Condition::LapsesBelow(5)),
+                (true, Condition::LapsesEqual(3)),
+                (true, Condition::Reviewed(7)),
+                (true, Condition::Missed(1)),
+                (true, Condition::Text(String::from("hola"))),
+                (true, Condition::Text(String::from("-"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_numbers_are_searched_as_text() {
+        assert_eq!(
+            Filter::parse("lapses>many reviewed:x").conditions,
+            vec![
+                (true, Condition::Text(String::from("lapses>many"))),
+                (true, Condition::Text(String::from("reviewed:x"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn huge_day_counts_do_not_overflow() {
+        assert_eq!(
+            Filter::parse("reviewed:99999999999999999").conditions,
+            vec![(
+                true,
+                Condition::Text(String::from("reviewed:99999999999999999"))
+            )]
+        );
+        let mut missed = card("Missed");
+        missed.schedule.reviews.push(Review {
+            time: start(),
+            grade: Grade::Again,
+        });
+        //Fits in a duration, but goes back further than a date can.
+        for query in ["reviewed:100000000", "missed:100000000"] {
+            assert!(matches!(
+                Filter::parse(query).conditions[0].1,
+                Condition::Reviewed(_) | Condition::Missed(_)
+            ));
+            assert!(!Filter::parse(query).matches(&missed, "Deck", start()));
+        }
+        assert!(Filter::parse("missed:100000").matches(&missed, "Deck", start()));
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert!(Filter::parse("").matches(&card("Hola"), "Spanish", start()));
+    }
+
+    #[test]
+    fn matches_deck_tag_and_text() {
+        let mut hola = card("Hola");
+        hola.tags = vec![String::from("greetings")];
+        assert!(Filter::parse("deck:span").matches(&hola, "Spanish 101", start()));
+        assert!(!Filter::parse("deck:french").matches(&hola, "Spanish 101", start()));
+        assert!(Filter::parse("tag:#Greetings").matches(&hola, "Spanish", start()));
+        assert!(!Filter::parse("-tag:greetings").matches(&hola, "Spanish", start()));
+        assert!(Filter::parse("HOLA answer").matches(&hola, "Spanish", start()));
+        assert!(!Filter::parse("hola adios").matches(&hola, "Spanish", start()));
+    }
+
+    #[test]
+    fn matches_scheduling_state() {
+        let new = card("New");
+        let due = reviewed(card("Due"), 3, Grade::Good);
+        let mut missed = reviewed(card("Missed"), 0, Grade::Again);
+        missed.schedule.lapses = 3;
+        missed.schedule.learning_step = Some(0);
+        let is = |query: &str, card: &Card| Filter::parse(query).matches(card, "Deck", start());
+        assert!(is("is:new", &new) && !is("is:new", &due));
+        assert!(is("is:due", &due) && !is("is:due", &new) && !is("is:due", &missed));
+        assert!(is("is:learning", &missed) && !is("is:learning", &due));
+        assert!(is("reviewed:5", &due) && !is("reviewed:2", &due) && !is("reviewed:5", &new));
+        assert!(is("missed:1", &missed) && !is("missed:7", &due));
+        assert!(is("lapses>2 lapses<4 lapses=3", &missed));
+        assert!(is("lapses=0", &new) && !is("lapses>0", &new));
+    }
+
+    #[test]
+    fn matches_suspended_and_leeches() {
+        let mut leech = card("Leech");
+        leech.leech = true;
+        leech.suspended = true;
+        assert!(Filter::parse("is:leech is:suspended").matches(&leech, "Deck", start()));
+        assert!(!Filter::parse("-is:suspended").matches(&leech, "Deck", start()));
+        assert!(Filter::parse("-is:leech").matches(&card("Fine"), "Deck", start()));
+    }
+}
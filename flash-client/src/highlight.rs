@@ -0,0 +1,69 @@
+use std::sync::OnceLock;
+
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{FontStyle, Style as CodeStyle, Theme, ThemeSet},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+use tui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+
+const THEME: &str = "base16-ocean.dark";
+
+//The grammars and themes bundled with syntect, loaded the first time a code block is shown.
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static CODE_THEME: OnceLock<Theme> = OnceLock::new();
+    CODE_THEME.get_or_init(|| {
+        ThemeSet::load_defaults()
+            .themes
+            .remove(THEME)
+            .unwrap_or_default()
+    })
+}
+
+//Colours a code block line by line, a language that isn't known is shown as plain text.
+pub fn highlight(code: &str, language: &str) -> Vec<Vec<Span<'static>>> {
+    let syntaxes = syntaxes();
+    let syntax = syntaxes
+        .find_syntax_by_token(language)
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+    let mut highlighter = HighlightLines::new(syntax, theme());
+    LinesWithEndings::from(code)
+        .map(|line| match highlighter.highlight_line(line, syntaxes) {
+            Ok(ranges) => ranges
+                .into_iter()
+                .map(|(style, text)| (style, text.trim_end_matches(['\r', '\n'])))
+                .filter(|(_, text)| !text.is_empty())
+                .map(|(style, text)| Span::styled(text.to_string(), convert(style)))
+                .collect(),
+            Err(_) => vec![Span::raw(line.trim_end_matches(['\r', '\n']).to_string())],
+        })
+        .collect()
+}
+
+//Only the foreground is used so code sits on the same background as the rest of the card.
+fn convert(style: CodeStyle) -> Style {
+    let mut converted = Style::default().fg(Color::Rgb(
+        style.foreground.r,
+        style.foreground.g,
+        style.foreground.b,
+    ));
+    if style.font_style.contains(FontStyle::BOLD) {
+        converted = converted.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        converted = converted.add_modifier(Modifier::ITALIC);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        converted = converted.add_modifier(Modifier::UNDERLINED);
+    }
+    converted
+}
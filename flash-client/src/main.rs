@@ -1,13 +1,39 @@
-use std::io::Error;
+use std::{env, error::Error};
 
 use screen::{Screen, ScreenState};
 
+mod answer;
 mod card;
+mod cloze;
+mod commands;
 mod deck;
+mod exam;
+mod filter;
+mod highlight;
+mod markdown;
+mod matching;
+mod note_type;
+mod quiz;
+mod review_log;
+mod scheduler;
 mod screen;
+mod session;
+mod settings;
+#[cfg(test)]
+mod test_util;
+mod timer;
 mod util;
 
-fn main() -> Result<(), Error> {
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("optimize") => {
+            let deck_name = args.get(1).ok_or("Usage: flash-client optimize <deck>")?;
+            return commands::optimize(deck_name);
+        }
+        Some(command) => return Err(format!("Unknown command '{}'.", command))?,
+        None => (),
+    }
     let mut screen = Screen::new(ScreenState::LocalMenu).expect("Terminal could not be created.");
     screen.run().expect("Terminal failed during content loop.");
     Ok(())
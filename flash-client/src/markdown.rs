@@ -0,0 +1,609 @@
+use pulldown_cmark::{
+    Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd, TextMergeStream,
+};
+use tui::{
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{cloze, highlight};
+
+//Turns the Markdown of a card section into styled lines that fit in the given width.
+//Code blocks aren't wrapped, they are shifted left by the scroll instead.
+//For a cloze item the active number is hidden, or picked out once it is revealed.
+pub fn render(
+    text: &str,
+    width: usize,
+    scroll: usize,
+    cloze: Option<(u32, bool)>,
+) -> Vec<Spans<'static>> {
+    let mut renderer = Renderer::new(width.max(1), scroll, cloze);
+    renderer.read(text);
+    renderer.lines
+}
+
+//How far the widest line of code runs past the given width, measured without highlighting it.
+pub fn overflow(text: &str, width: usize, cloze: Option<(u32, bool)>) -> usize {
+    let mut renderer = Renderer::new(width.max(1), 0, cloze);
+    renderer.highlight = false;
+    renderer.read(text);
+    renderer.widest.saturating_sub(width)
+}
+
+#[derive(Default)]
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+    header: bool,
+}
+
+struct Renderer {
+    width: usize,
+    scroll: usize,
+    //The widest line of code seen, counting what it is indented by.
+    widest: usize,
+    highlight: bool,
+    cloze: Option<(u32, bool)>,
+    lines: Vec<Spans<'static>>,
+    //The text of the block being read, wrapped into lines once the block is over.
+    inline: Vec<Span<'static>>,
+    styles: Vec<Style>,
+    quotes: usize,
+    //Every open list and the number of its next item, bullet lists have no number.
+    lists: Vec<Option<u64>>,
+    //How far each open list item indents its lines, and the marker for the first one.
+    indents: Vec<usize>,
+    marker: Option<String>,
+    //Blocks are kept apart by an empty line.
+    gap: bool,
+    //The text and language of the code block being read.
+    code: Option<(String, String)>,
+    table: Option<Table>,
+}
+
+impl Renderer {
+    fn new(width: usize, scroll: usize, cloze: Option<(u32, bool)>) -> Self {
+        Renderer {
+            width,
+            scroll,
+            widest: 0,
+            highlight: true,
+            cloze,
+            lines: Vec::new(),
+            inline: Vec::new(),
+            styles: Vec::new(),
+            quotes: 0,
+            lists: Vec::new(),
+            indents: Vec::new(),
+            marker: None,
+            gap: false,
+            code: None,
+            table: None,
+        }
+    }
+
+    //Text is merged first so a deletion isn't split between two pieces.
+    fn read(&mut self, text: &str) {
+        let parser = Parser::new_ext(text, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH);
+        for event in TextMergeStream::new(parser) {
+            self.event(event);
+        }
+        self.flush();
+    }
+
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    fn push_style(&mut self, style: Style) {
+        self.styles.push(self.style().patch(style));
+    }
+
+    fn text(&mut self, text: String) {
+        match &mut self.code {
+            Some((code, _)) => code.push_str(&text),
+            None => {
+                let spans = self.spans(&text, self.style());
+                self.inline.extend(spans);
+            }
+        }
+    }
+
+    fn spans(&self, text: &str, style: Style) -> Vec<Span<'static>> {
+        match self.cloze {
+            Some((number, revealed)) => cloze::render(text, number, revealed, style),
+            None => vec![Span::styled(text.to_string(), style)],
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.text(text.to_string()),
+            Event::Code(code) => {
+                let spans = self.spans(&code, self.style().fg(Color::Magenta));
+                self.inline.extend(spans);
+            }
+            Event::Html(html) | Event::InlineHtml(html) => self.text(html.to_string()),
+            Event::InlineMath(math) | Event::DisplayMath(math) => self.text(math.to_string()),
+            Event::FootnoteReference(name) => self.text(format!("[^{}]", name)),
+            //Lines of a section stay apart, like they were typed.
+            Event::SoftBreak | Event::HardBreak => self.flush(),
+            Event::Rule => {
+                self.start_block();
+                let mut line = self.prefix(false);
+                let width = self.width.saturating_sub(line_width(&line)).max(1);
+                line.push(Span::styled(
+                    "─".repeat(width),
+                    Style::default().fg(Color::DarkGray),
+                ));
+                self.lines.push(Spans::from(line));
+                self.gap = true;
+            }
+            Event::TaskListMarker(checked) => {
+                self.text(String::from(if checked { "[x] " } else { "[ ] " }))
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.start_block(),
+            Tag::Heading { level, .. } => {
+                self.start_block();
+                self.push_style(match level {
+                    HeadingLevel::H1 => Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                    HeadingLevel::H2 => Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                    _ => Style::default().add_modifier(Modifier::BOLD),
+                });
+            }
+            Tag::BlockQuote(_) => {
+                self.start_block();
+                self.quotes += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.start_block();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((String::new(), language));
+            }
+            Tag::List(first) => {
+                if self.lists.is_empty() {
+                    self.start_block();
+                } else {
+                    self.flush();
+                }
+                self.lists.push(first);
+            }
+            Tag::Item => {
+                self.flush();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => String::from("• "),
+                };
+                self.indents.push(marker.width());
+                self.marker = Some(marker);
+            }
+            Tag::Table(alignments) => {
+                self.start_block();
+                self.table = Some(Table {
+                    alignments,
+                    ..Table::default()
+                });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::Emphasis => self.push_style(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => {
+                self.push_style(Style::default().add_modifier(Modifier::CROSSED_OUT))
+            }
+            Tag::Link { .. } => self.push_style(
+                Style::default()
+                    .fg(Color::Blue)
+                    .add_modifier(Modifier::UNDERLINED),
+            ),
+            Tag::Image { .. } => self.push_style(Style::default().add_modifier(Modifier::ITALIC)),
+            _ => (),
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => {
+                self.flush();
+                self.gap = true;
+            }
+            TagEnd::Heading(_) => {
+                self.flush();
+                self.styles.pop();
+                self.gap = true;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quotes = self.quotes.saturating_sub(1);
+                self.gap = true;
+            }
+            TagEnd::CodeBlock => {
+                let (mut code, language) = self.code.take().unwrap_or_default();
+                //Deletions in code are filled in before highlighting, so the code still reads right.
+                if self.cloze.is_some() {
+                    code = self
+                        .spans(&code, Style::default())
+                        .into_iter()
+                        .map(|span| span.content.into_owned())
+                        .collect();
+                }
+                let code = code.replace('\t', "    ");
+                let code_lines = if self.highlight {
+                    highlight::highlight(&code, &language)
+                } else {
+                    code.lines()
+                        .map(|line| vec![Span::raw(line.to_string())])
+                        .collect()
+                };
+                for code_line in code_lines {
+                    let mut line = self.prefix(false);
+                    let indent = line_width(&line);
+                    self.widest = self.widest.max(indent + line_width(&code_line));
+                    line.extend(clip(
+                        code_line,
+                        self.scroll,
+                        self.width.saturating_sub(indent),
+                    ));
+                    self.lines.push(Spans::from(line));
+                }
+                self.gap = true;
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.gap = true;
+                }
+            }
+            TagEnd::Item => {
+                self.flush();
+                self.indents.pop();
+                self.marker = None;
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.header = true;
+                }
+            }
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.inline);
+                if let Some(row) = self.table.as_mut().and_then(|table| table.rows.last_mut()) {
+                    row.push(cell);
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.table(table);
+                }
+                self.gap = true;
+            }
+            TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Link
+            | TagEnd::Image => {
+                self.styles.pop();
+            }
+            _ => (),
+        }
+    }
+
+    fn start_block(&mut self) {
+        self.flush();
+        if self.gap && !self.lines.is_empty() {
+            let line = self.prefix(false);
+            self.lines.push(Spans::from(line));
+        }
+        self.gap = false;
+    }
+
+    //Quotes are marked down the left, lines of a list item line up after its marker.
+    fn prefix(&mut self, first: bool) -> Vec<Span<'static>> {
+        let mut prefix = Vec::new();
+        if self.quotes > 0 {
+            prefix.push(Span::styled(
+                "│ ".repeat(self.quotes),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        let indent: usize = self.indents.iter().sum();
+        let marker = if first { self.marker.take() } else { None };
+        match marker {
+            Some(marker) => {
+                let outer = indent - self.indents.last().copied().unwrap_or(0);
+                prefix.push(Span::raw(" ".repeat(outer)));
+                prefix.push(Span::styled(marker, Style::default().fg(Color::Yellow)));
+            }
+            None if indent > 0 => prefix.push(Span::raw(" ".repeat(indent))),
+            None => (),
+        }
+        prefix
+    }
+
+    fn flush(&mut self) {
+        if self.inline.is_empty() || self.table.is_some() {
+            return;
+        }
+        let spans = std::mem::take(&mut self.inline);
+        let mut first = true;
+        let width = {
+            let indent: usize = self.indents.iter().sum();
+            self.width.saturating_sub(self.quotes * 2 + indent).max(1)
+        };
+        for wrapped in wrap(spans, width) {
+            let mut line = self.prefix(first);
+            line.extend(wrapped);
+            self.lines.push(Spans::from(line));
+            first = false;
+        }
+    }
+
+    //Columns are as wide as their widest cell, the header is set off by a line.
+    fn table(&mut self, table: Table) {
+        let columns = table.rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                table
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| line_width(cell))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let border = Style::default().fg(Color::DarkGray);
+        for (index, row) in table.rows.into_iter().enumerate() {
+            let heading = table.header && index == 0;
+            let mut line = self.prefix(false);
+            for (column, width) in widths.iter().enumerate() {
+                if column > 0 {
+                    line.push(Span::styled(" │ ", border));
+                }
+                let mut cell = row.get(column).cloned().unwrap_or_default();
+                if heading {
+                    for span in cell.iter_mut() {
+                        span.style = span.style.add_modifier(Modifier::BOLD);
+                    }
+                }
+                let space = width - line_width(&cell);
+                let before = match table.alignments.get(column) {
+                    Some(Alignment::Right) => space,
+                    Some(Alignment::Center) => space / 2,
+                    _ => 0,
+                };
+                line.push(Span::raw(" ".repeat(before)));
+                line.extend(cell);
+                line.push(Span::raw(" ".repeat(space - before)));
+            }
+            self.lines.push(Spans::from(line));
+            if heading {
+                let mut line = self.prefix(false);
+                line.push(Span::styled(
+                    widths
+                        .iter()
+                        .map(|width| "─".repeat(*width))
+                        .collect::<Vec<String>>()
+                        .join("─┼─"),
+                    border,
+                ));
+                self.lines.push(Spans::from(line));
+            }
+        }
+    }
+}
+
+fn line_width(spans: &[Span]) -> usize {
+    spans.iter().map(|span| span.content.width()).sum()
+}
+
+//Keeps the columns of a line of code between the scroll and the edge of the panel.
+fn clip(spans: Vec<Span<'static>>, scroll: usize, width: usize) -> Vec<Span<'static>> {
+    let mut clipped = Vec::new();
+    let mut column = 0;
+    for span in spans {
+        let mut text = String::new();
+        for c in span.content.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if column >= scroll && column + char_width <= scroll + width {
+                text.push(c);
+            }
+            column += char_width;
+        }
+        if !text.is_empty() {
+            clipped.push(Span::styled(text, span.style));
+        }
+    }
+    clipped
+}
+
+//Runs of spaces and runs of everything else, lines are only broken between them.
+fn pieces(text: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut blank = None;
+    for (index, c) in text.char_indices() {
+        if blank.is_some_and(|blank| blank != c.is_whitespace()) {
+            pieces.push(&text[start..index]);
+            start = index;
+        }
+        blank = Some(c.is_whitespace());
+    }
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+    pieces
+}
+
+fn wrap(spans: Vec<Span<'static>>, width: usize) -> Vec<Vec<Span<'static>>> {
+    let mut lines = vec![Vec::new()];
+    let mut used = 0;
+    for span in spans {
+        for piece in pieces(&span.content) {
+            let blank = piece.chars().all(char::is_whitespace);
+            if used > 0 && used + piece.width() > width {
+                lines.push(Vec::new());
+                used = 0;
+                //Spaces a line is broken at are dropped.
+                if blank {
+                    continue;
+                }
+            }
+            //Words longer than a whole line are split where the line runs out.
+            let mut rest = piece;
+            while used + rest.width() > width {
+                let mut split = 0;
+                let mut taken = used;
+                for (index, c) in rest.char_indices() {
+                    taken += c.width().unwrap_or(0);
+                    if taken > width && split > 0 {
+                        break;
+                    }
+                    split = index + c.len_utf8();
+                }
+                if let Some(line) = lines.last_mut() {
+                    line.push(Span::styled(rest[..split].to_string(), span.style));
+                }
+                lines.push(Vec::new());
+                used = 0;
+                rest = &rest[split..];
+            }
+            if !rest.is_empty() {
+                used += rest.width();
+                if let Some(line) = lines.last_mut() {
+                    line.push(Span::styled(rest.to_string(), span.style));
+                }
+            }
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str, width: usize, scroll: usize, cloze: Option<(u32, bool)>) -> Vec<String> {
+        render(text, width, scroll, cloze)
+            .into_iter()
+            .map(|line| {
+                line.0
+                    .into_iter()
+                    .map(|span| span.content.into_owned())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn paragraphs_wrap_between_words() {
+        assert_eq!(
+            lines("one two three four", 9, 0, None),
+            vec!["one two ", "three ", "four"]
+        );
+        assert_eq!(
+            lines("abcdefghijklmno", 6, 0, None),
+            vec!["abcdef", "ghijkl", "mno"]
+        );
+    }
+
+    #[test]
+    fn list_items_wrap_under_their_text() {
+        assert_eq!(
+            lines(
+                "- alpha beta gamma\n- delta\n\n1. one two three\n2. four",
+                10,
+                0,
+                None
+            ),
+            vec![
+                "• alpha ",
+                "  beta ",
+                "  gamma",
+                "• delta",
+                "",
+                "1. one two",
+                "   three",
+                "2. four"
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_are_marked_on_every_wrapped_line() {
+        assert_eq!(
+            lines("> quoted text that wraps\n\nafter", 12, 0, None),
+            vec!["│ quoted ", "│ text that ", "│ wraps", "", "after"]
+        );
+    }
+
+    #[test]
+    fn wide_code_is_clipped_and_scrolled() {
+        let code = "```\nabcdefghij\n\tx\n```";
+        assert_eq!(lines(code, 4, 0, None), vec!["abcd", "    "]);
+        assert_eq!(lines(code, 4, 2, None), vec!["cdef", "  x"]);
+        assert_eq!(lines(code, 4, 8, None), vec!["ij", ""]);
+        assert_eq!(overflow(code, 4, None), 6);
+        assert_eq!(overflow(code, 20, None), 0);
+        //Code in a list counts the indent it sits behind.
+        let listed = "- item\n\n  ```\n  abcdefghij\n  ```";
+        assert_eq!(lines(listed, 8, 0, None), vec!["• item", "  ", "  abcdef"]);
+        assert_eq!(overflow(listed, 8, None), 4);
+    }
+
+    #[test]
+    fn cloze_inside_inline_code() {
+        let text = "Use `{{c1::println!}}` here";
+        assert_eq!(lines(text, 40, 0, Some((1, false))), vec!["Use [...] here"]);
+        let revealed = render(text, 40, 0, Some((1, true)));
+        let answer = revealed[0]
+            .0
+            .iter()
+            .find(|span| span.content == "println!")
+            .unwrap();
+        assert_eq!(answer.style.fg, Some(Color::Cyan));
+        //Without a cloze item the markup is shown as written.
+        assert_eq!(lines(text, 40, 0, None), vec!["Use {{c1::println!}} here"]);
+    }
+
+    #[test]
+    fn cloze_inside_code_blocks() {
+        let code = "```rust\nlet {{c1::answer::hint}} = 42;\n```";
+        assert_eq!(
+            lines(code, 40, 0, Some((1, false))),
+            vec!["let [hint] = 42;"]
+        );
+        assert_eq!(
+            lines(code, 40, 0, Some((1, true))),
+            vec!["let answer = 42;"]
+        );
+        assert_eq!(overflow(code, 10, Some((1, false))), 6);
+        assert_eq!(overflow(code, 10, Some((1, true))), 6);
+        assert_eq!(overflow(code, 10, Some((2, false))), 6);
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use rand::{seq::SliceRandom, thread_rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{card::Variant, deck::Deck, util};
+
+pub const MATCHING_LOG_FILE: &str = "matching_log.jsonl";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Column {
+    #[default]
+    Fronts,
+    Backs,
+}
+
+//Card fronts on one side and their backs shuffled on the other, to be paired up as quickly as possible.
+#[derive(Clone, Debug, Default)]
+pub struct MatchGame {
+    pub fronts: Vec<String>,
+    //Each back keeps the index of the front it belongs to.
+    pub backs: Vec<(usize, String)>,
+    pub matched: Vec<bool>,
+    pub selected: [Option<usize>; 2],
+    //The last wrong pair stays marked until the next pick.
+    pub wrong: Option<(usize, usize)>,
+    pub errors: u32,
+    pub started: DateTime<Utc>,
+    pub finished: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MatchResult {
+    pub deck: String,
+    pub time: DateTime<Utc>,
+    pub pairs: usize,
+    pub errors: u32,
+    pub duration_ms: i64,
+}
+
+impl MatchGame {
+    //Pairs come from cards with a front and a back, backs that repeat would make the game ambiguous.
+    pub fn new(deck: &Deck, now: DateTime<Utc>) -> Self {
+        let mut rng = thread_rng();
+        let mut order = deck.study_order();
+        order.shuffle(&mut rng);
+        let mut fronts = Vec::new();
+        let mut backs: Vec<(usize, String)> = Vec::new();
+        for index in order {
+            if fronts.len() >= deck.settings.matching_pairs {
+                break;
+            }
+            let card = &deck.contents[index];
+            //Cards of a note type are matched by the front and back of their first template.
+            let variant = match card.note {
+                Some(_) => Variant::Template(0),
+                None => Variant::Forward,
+            };
+            if card.suspended || card.page_count(variant) < 2 {
+                continue;
+            }
+            let (Some(front), Some(back)) = (card.section(variant, 0), card.section(variant, 1))
+            else {
+                continue;
+            };
+            let back = back.trim().to_string();
+            if back.is_empty() || backs.iter().any(|(_, other)| *other == back) {
+                continue;
+            }
+            backs.push((fronts.len(), back));
+            fronts.push(front.trim().to_string());
+        }
+        backs.shuffle(&mut rng);
+        MatchGame {
+            matched: vec![false; fronts.len()],
+            fronts,
+            backs,
+            started: now,
+            ..MatchGame::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.fronts.len()
+    }
+
+    pub fn is_matched(&self, column: Column, row: usize) -> bool {
+        match column {
+            Column::Fronts => self.matched.get(row).copied().unwrap_or(true),
+            Column::Backs => self
+                .backs
+                .get(row)
+                .map(|(front, _)| self.matched[*front])
+                .unwrap_or(true),
+        }
+    }
+
+    //Picks an entry, once one is picked on each side they are checked as a pair.
+    pub fn select(&mut self, column: Column, row: usize, now: DateTime<Utc>) {
+        if self.finished.is_some() || self.is_matched(column, row) {
+            return;
+        }
+        self.wrong = None;
+        self.selected[column as usize] = Some(row);
+        if let [Some(front), Some(back)] = self.selected {
+            if self.backs[back].0 == front {
+                self.matched[front] = true;
+                if self.matched.iter().all(|matched| *matched) {
+                    self.finished = Some(now);
+                }
+            } else {
+                self.errors += 1;
+                self.wrong = Some((front, back));
+            }
+            self.selected = [None, None];
+        }
+    }
+
+    pub fn elapsed(&self, now: DateTime<Utc>) -> Duration {
+        self.finished.unwrap_or(now) - self.started
+    }
+
+    pub fn result(&self, deck: &Deck, now: DateTime<Utc>) -> MatchResult {
+        MatchResult {
+            deck: deck.deck_title.clone(),
+            time: self.started,
+            pairs: self.len(),
+            errors: self.errors,
+            duration_ms: self.elapsed(now).num_milliseconds(),
+        }
+    }
+}
+
+//Every finished game is appended here, one JSON record per line like the review log.
+pub struct MatchingLog {
+    path: PathBuf,
+}
+
+impl MatchingLog {
+    pub fn new(config_directory: &Path) -> Self {
+        MatchingLog {
+            path: config_directory.join(MATCHING_LOG_FILE),
+        }
+    }
+
+    pub fn append(&self, result: &MatchResult) -> Result<(), Box<dyn Error>> {
+        util::append_json_line(&self.path, result)
+    }
+}
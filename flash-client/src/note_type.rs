@@ -0,0 +1,188 @@
+use ini::Ini;
+
+//Lays a note's fields out into the front and back of one study card.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    pub name: String,
+    pub front: String,
+    pub back: String,
+}
+
+//Names the fields of every card in a deck, a card's sections hold the field values in order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NoteType {
+    pub name: String,
+    pub fields: Vec<String>,
+    pub templates: Vec<Template>,
+}
+
+impl NoteType {
+    //Read from the [Note] section of deck.ini, every [Template Name] section adds a card per note.
+    pub fn read(config: &Ini) -> Option<Self> {
+        let section = config.section(Some("Note"))?;
+        let fields: Vec<String> = section
+            .get("fields")?
+            .split(',')
+            .map(|field| field.trim().to_string())
+            .filter(|field| !field.is_empty())
+            .collect();
+        if fields.is_empty() {
+            return None;
+        }
+        let mut templates: Vec<Template> = config
+            .iter()
+            .filter_map(|(name, section)| {
+                let name = name?.strip_prefix("Template")?.trim();
+                Some(Template {
+                    name: name.to_string(),
+                    front: section.get("front")?.to_string(),
+                    back: section.get("back").unwrap_or_default().to_string(),
+                })
+            })
+            .collect();
+        //Without templates the first field is asked and the rest make up the answer.
+        if templates.is_empty() {
+            templates.push(Template {
+                name: String::from("Card"),
+                front: format!("{{{{{}}}}}", fields[0]),
+                back: fields[1..]
+                    .iter()
+                    .map(|field| format!("{{{{{}}}}}", field))
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            });
+        }
+        Some(NoteType {
+            name: section.get("name").unwrap_or_default().trim().to_string(),
+            fields,
+            templates,
+        })
+    }
+
+    pub fn field_name(&self, index: usize) -> Option<&str> {
+        self.fields.get(index).map(|field| field.as_str())
+    }
+
+    pub fn front(&self, template: usize, values: &[String]) -> Option<String> {
+        Some(self.render(&self.templates.get(template)?.front, values))
+    }
+
+    pub fn back(&self, template: usize, values: &[String]) -> Option<String> {
+        Some(self.render(&self.templates.get(template)?.back, values))
+    }
+
+    //Replaces every {{Field}} with the card's value for it, unknown names are left as typed.
+    fn render(&self, text: &str, values: &[String]) -> String {
+        let mut rendered = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            rendered.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("}}") else {
+                rest = &rest[start..];
+                break;
+            };
+            let name = after[..end].trim();
+            match self
+                .fields
+                .iter()
+                .position(|field| field.eq_ignore_ascii_case(name))
+            {
+                Some(index) => {
+                    rendered.push_str(values.get(index).map(|value| value.as_str()).unwrap_or(""))
+                }
+                None => rendered.push_str(&rest[start..start + end + 4]),
+            }
+            rest = &after[end + 2..];
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(text: &str) -> Option<NoteType> {
+        NoteType::read(&Ini::load_from_str(text).unwrap())
+    }
+
+    fn values(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    const VOCABULARY: &str = r"
+[Note]
+name = Vocabulary
+fields = Word, Meaning, , Example
+
+[Template Recognition]
+front = {{Word}}
+back = {{Meaning}}\n{{Example}}
+
+[Template Recall]
+front = {{meaning}}
+back = {{ Word }}
+
+[Template Broken]
+back = {{Word}}
+";
+
+    #[test]
+    fn reads_fields_and_templates() {
+        let note = read(VOCABULARY).unwrap();
+        assert_eq!(note.name, "Vocabulary");
+        assert_eq!(note.fields, vec!["Word", "Meaning", "Example"]);
+        assert_eq!(note.field_name(2), Some("Example"));
+        assert_eq!(note.field_name(3), None);
+        //A template without a front is left out.
+        let names: Vec<&str> = note
+            .templates
+            .iter()
+            .map(|template| template.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Recognition", "Recall"]);
+    }
+
+    #[test]
+    fn renders_fields_into_templates() {
+        let note = read(VOCABULARY).unwrap();
+        let card = values(&["gato", "cat", "El gato duerme."]);
+        assert_eq!(note.front(0, &card).unwrap(), "gato");
+        assert_eq!(note.back(0, &card).unwrap(), "cat\nEl gato duerme.");
+        assert_eq!(note.front(1, &card).unwrap(), "cat");
+        assert_eq!(note.back(1, &card).unwrap(), "gato");
+        assert_eq!(note.front(2, &card), None);
+        //Fields without a value come out empty.
+        assert_eq!(note.back(0, &values(&["perro"])).unwrap(), "\n");
+    }
+
+    #[test]
+    fn unknown_and_unclosed_placeholders_stay_as_typed() {
+        let note = read(VOCABULARY).unwrap();
+        let card = values(&["gato", "cat"]);
+        assert_eq!(
+            note.render("{{Word}} {{Plural}} {{Meaning", &card),
+            "gato {{Plural}} {{Meaning"
+        );
+        assert_eq!(note.render("no fields", &card), "no fields");
+    }
+
+    #[test]
+    fn first_field_is_asked_without_templates() {
+        let note = read("[Note]\nfields = Country, Capital, Currency").unwrap();
+        assert_eq!(note.templates.len(), 1);
+        assert_eq!(note.templates[0].name, "Card");
+        let card = values(&["Peru", "Lima", "Sol"]);
+        assert_eq!(note.front(0, &card).unwrap(), "Peru");
+        assert_eq!(note.back(0, &card).unwrap(), "Lima\nSol");
+    }
+
+    #[test]
+    fn needs_a_note_section_with_fields() {
+        assert_eq!(read("[Scheduler]\nalgorithm = fsrs"), None);
+        assert_eq!(read("[Note]\nname = Empty"), None);
+        assert_eq!(read("[Note]\nfields = , ,"), None);
+    }
+}
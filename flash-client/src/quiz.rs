@@ -0,0 +1,126 @@
+use rand::{seq::SliceRandom, thread_rng};
+
+use crate::{
+    card::Variant,
+    deck::{Deck, Item},
+};
+
+//How many choices a question offers, including the right one.
+pub const QUIZ_CHOICES: usize = 4;
+//The section of a card that holds its answer, the first one being the prompt.
+const ANSWER_SECTION: usize = 1;
+
+//A multiple choice question made from a card, the wrong choices come from the other cards in the deck.
+#[derive(Clone, Debug)]
+pub struct Question {
+    pub choices: Vec<String>,
+    pub correct: usize,
+    pub chosen: Option<usize>,
+}
+
+impl Question {
+    //Cards without an answer section can't be asked as a question.
+    pub fn new(deck: &Deck, item: Item) -> Option<Self> {
+        let answer = answer_of(deck, item)?;
+        //Cloze deletions are only mixed with other deletions, which may come from the same card,
+        //and plain answers only with the answers of other plain cards.
+        let cloze = is_cloze(item.variant);
+        let mut others: Vec<String> = (0..deck.len())
+            .filter(|card| {
+                deck.items(vec![*card])
+                    .iter()
+                    .any(|other| is_cloze(other.variant))
+                    == cloze
+            })
+            .flat_map(|card| match item.variant {
+                Variant::Cloze(_) => deck.items(vec![card]),
+                variant => vec![Item { card, variant }],
+            })
+            .filter(|other| other.card != item.card || other.variant != item.variant)
+            .filter_map(|other| answer_of(deck, other))
+            .filter(|other| *other != answer)
+            .collect();
+        others.sort();
+        others.dedup();
+        let mut rng = thread_rng();
+        let mut choices: Vec<String> = others
+            .choose_multiple(&mut rng, QUIZ_CHOICES - 1)
+            .cloned()
+            .collect();
+        choices.push(answer.clone());
+        choices.shuffle(&mut rng);
+        let correct = choices.iter().position(|choice| *choice == answer)?;
+        Some(Question {
+            choices,
+            correct,
+            chosen: None,
+        })
+    }
+
+    pub fn is_answered(&self) -> bool {
+        self.chosen.is_some()
+    }
+
+    pub fn is_correct(&self) -> bool {
+        self.chosen == Some(self.correct)
+    }
+}
+
+fn is_cloze(variant: Variant) -> bool {
+    matches!(variant, Variant::Cloze(_))
+}
+
+fn answer_of(deck: &Deck, item: Item) -> Option<String> {
+    let card = &deck.contents[item.card];
+    let answer = match item.variant {
+        Variant::Cloze(_) => card.answer(item.variant),
+        variant => card.section(variant, ANSWER_SECTION)?,
+    };
+    Some(answer.trim().to_string()).filter(|answer| !answer.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{card, deck, item};
+
+    fn mixed_deck() -> Deck {
+        let mut cards = deck().contents.into_vec();
+        cards.push(card(
+            "{{c1::Berlin}} is the capital of {{c2::Germany}}",
+            "Its river is the {{c3::Spree}}",
+        ));
+        cards.push(card("{{c1::Bern}} is the capital of Switzerland", ""));
+        Deck::new("Capitals", cards)
+    }
+
+    #[test]
+    fn plain_questions_never_offer_cloze_markup() {
+        let deck = mixed_deck();
+        for _ in 0..20 {
+            let question = Question::new(&deck, item(0)).unwrap();
+            assert_eq!(question.choices.len(), QUIZ_CHOICES);
+            assert_eq!(question.choices[question.correct], "Paris");
+            assert!(question
+                .choices
+                .iter()
+                .all(|choice| ["Paris", "Madrid", "Rome", "Lima"].contains(&choice.as_str())));
+        }
+    }
+
+    #[test]
+    fn cloze_questions_only_offer_deletions() {
+        let deck = mixed_deck();
+        let berlin = Item {
+            card: 4,
+            variant: Variant::Cloze(1),
+        };
+        for _ in 0..20 {
+            let question = Question::new(&deck, berlin).unwrap();
+            assert_eq!(question.choices[question.correct], "Berlin");
+            let mut choices = question.choices.clone();
+            choices.sort();
+            assert_eq!(choices, ["Berlin", "Bern", "Germany", "Spree"]);
+        }
+    }
+}
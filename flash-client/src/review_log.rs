@@ -0,0 +1,49 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{card::Variant, scheduler::Grade, session::StudyMode, util};
+
+pub const REVIEW_LOG_FILE: &str = "review_log.jsonl";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReviewRecord {
+    //Records from before cards had ids only have the title.
+    #[serde(default)]
+    pub card_id: Option<Uuid>,
+    pub card: String,
+    #[serde(default)]
+    pub variant: Variant,
+    pub deck: String,
+    //Cram and quiz answers are logged under Cram, they don't move the card's schedule.
+    #[serde(default)]
+    pub mode: StudyMode,
+    pub time: DateTime<Utc>,
+    pub grade: Grade,
+    pub response_ms: u64,
+    //Intervals are in days, a previous interval of 0 means the card was new.
+    pub previous_interval: u32,
+    pub next_interval: u32,
+}
+
+//Append only history of every graded answer, one JSON record per line so a crash can only lose the last one.
+pub struct ReviewLog {
+    path: PathBuf,
+}
+
+impl ReviewLog {
+    pub fn new(config_directory: &Path) -> Self {
+        ReviewLog {
+            path: config_directory.join(REVIEW_LOG_FILE),
+        }
+    }
+
+    pub fn append(&self, record: &ReviewRecord) -> Result<(), Box<dyn Error>> {
+        util::append_json_line(&self.path, record)
+    }
+}
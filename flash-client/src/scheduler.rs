@@ -0,0 +1,859 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Grade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Grade {
+    pub fn from_key(key: char) -> Option<Self> {
+        match key {
+            '1' => Some(Grade::Again),
+            '2' => Some(Grade::Hard),
+            '3' => Some(Grade::Good),
+            '4' => Some(Grade::Easy),
+            _ => None,
+        }
+    }
+
+    //SM-2 works with a 0-5 quality scale, our four buttons map onto the upper part of it.
+    fn quality(&self) -> f64 {
+        match self {
+            Grade::Again => 1.0,
+            Grade::Hard => 3.0,
+            Grade::Good => 4.0,
+            Grade::Easy => 5.0,
+        }
+    }
+
+    fn rating(&self) -> f64 {
+        match self {
+            Grade::Again => 1.0,
+            Grade::Hard => 2.0,
+            Grade::Good => 3.0,
+            Grade::Easy => 4.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CardState {
+    New,
+    Learning,
+    Review,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Review {
+    pub time: DateTime<Utc>,
+    pub grade: Grade,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Schedule {
+    pub ease_factor: f64,
+    pub interval: u32,
+    pub repetitions: u32,
+    //A card that has never been reviewed has no due date and is always available.
+    pub due: Option<DateTime<Utc>>,
+    pub stability: f64,
+    pub difficulty: f64,
+    pub reviews: Vec<Review>,
+    pub leitner_box: usize,
+    //Some while the card is working through its learning or relearning steps.
+    pub learning_step: Option<usize>,
+    //How many times the card was forgotten after it had graduated.
+    pub lapses: u32,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            ease_factor: 2.5,
+            interval: 0,
+            repetitions: 0,
+            due: None,
+            stability: 0.0,
+            difficulty: 0.0,
+            reviews: Vec::new(),
+            leitner_box: 1,
+            learning_step: None,
+            lapses: 0,
+        }
+    }
+}
+
+impl Schedule {
+    pub fn is_new(&self) -> bool {
+        self.due.is_none()
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        !matches!(self.due, Some(due) if due > now)
+    }
+
+    pub fn state(&self) -> CardState {
+        if self.learning_step.is_some() {
+            CardState::Learning
+        } else if self.is_new() {
+            CardState::New
+        } else {
+            CardState::Review
+        }
+    }
+
+    pub fn last_review(&self) -> Option<DateTime<Utc>> {
+        self.reviews.last().map(|review| review.time)
+    }
+}
+
+//The study session asks the scheduler which due card to show next and hands every grade back to it.
+pub trait Scheduler {
+    fn next(&self, schedules: &[&Schedule], now: DateTime<Utc>) -> Option<usize>;
+
+    fn grade(&self, schedule: &mut Schedule, grade: Grade, now: DateTime<Utc>);
+}
+
+//Short steps a card has to get through before the scheduler takes over, both for new and for missed cards.
+pub struct LearningSteps {
+    learning: Vec<Duration>,
+    relearning: Vec<Duration>,
+}
+
+impl LearningSteps {
+    pub fn new(learning: Vec<Duration>, relearning: Vec<Duration>) -> Self {
+        LearningSteps {
+            learning,
+            relearning,
+        }
+    }
+
+    pub fn review(
+        &self,
+        scheduler: &dyn Scheduler,
+        schedule: &mut Schedule,
+        grade: Grade,
+        now: DateTime<Utc>,
+    ) {
+        //Only cards that have graduated once have an interval, so that tells learning and relearning apart.
+        let relearning = schedule.interval > 0;
+        match schedule.learning_step {
+            None if !schedule.is_new() => {
+                if grade == Grade::Again {
+                    schedule.lapses += 1;
+                }
+                scheduler.grade(schedule, grade, now);
+                if grade == Grade::Again && !self.relearning.is_empty() {
+                    schedule.learning_step = Some(0);
+                    schedule.due = Some(now + self.relearning[0]);
+                }
+            }
+            current => {
+                let steps = if relearning {
+                    &self.relearning
+                } else {
+                    &self.learning
+                };
+                let step = current.unwrap_or(0);
+                let next_step = match grade {
+                    Grade::Again => Some(0),
+                    Grade::Hard => Some(step),
+                    Grade::Good => Some(step + 1),
+                    Grade::Easy => None,
+                }
+                .filter(|next_step| *next_step < steps.len());
+                match next_step {
+                    Some(next_step) => {
+                        schedule.learning_step = Some(next_step);
+                        schedule.due = Some(now + steps[next_step]);
+                    }
+                    None => {
+                        schedule.learning_step = None;
+                        if relearning {
+                            schedule.due = Some(now + Duration::days(schedule.interval as i64));
+                        } else {
+                            scheduler.grade(schedule, grade, now);
+                        }
+                    }
+                }
+            }
+        }
+        schedule.reviews.push(Review { time: now, grade });
+    }
+}
+
+pub struct Sm2;
+
+impl Scheduler for Sm2 {
+    //Overdue reviews come first, oldest due date first, and new cards follow in deck order.
+    fn next(&self, schedules: &[&Schedule], now: DateTime<Utc>) -> Option<usize> {
+        schedules
+            .iter()
+            .enumerate()
+            .filter(|(_, schedule)| schedule.is_due(now))
+            .min_by_key(|(_, schedule)| (schedule.is_new(), schedule.due))
+            .map(|(index, _)| index)
+    }
+
+    fn grade(&self, schedule: &mut Schedule, grade: Grade, now: DateTime<Utc>) {
+        let quality = grade.quality();
+        if quality < 3.0 {
+            schedule.repetitions = 0;
+            schedule.interval = 1;
+        } else {
+            schedule.interval = match schedule.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (schedule.interval as f64 * schedule.ease_factor).round() as u32,
+            };
+            schedule.repetitions += 1;
+        }
+        schedule.ease_factor = (schedule.ease_factor
+            + (0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02)))
+            .max(1.3);
+        schedule.due = Some(now + Duration::days(schedule.interval as i64));
+    }
+}
+
+pub const LEITNER_DEFAULT_INTERVALS: [u32; 5] = [1, 2, 4, 8, 16];
+
+//Classic paper Leitner boxes, box n is reviewed every intervals[n - 1] days.
+pub struct Leitner {
+    intervals: Vec<u32>,
+}
+
+impl Leitner {
+    pub fn new(intervals: Vec<u32>) -> Self {
+        Leitner { intervals }
+    }
+}
+
+impl Scheduler for Leitner {
+    //Lower boxes hold the shakier cards so they are studied first.
+    fn next(&self, schedules: &[&Schedule], now: DateTime<Utc>) -> Option<usize> {
+        schedules
+            .iter()
+            .enumerate()
+            .filter(|(_, schedule)| schedule.is_due(now))
+            .min_by_key(|(_, schedule)| (schedule.is_new(), schedule.leitner_box, schedule.due))
+            .map(|(index, _)| index)
+    }
+
+    //A miss sends the card back to box 1, Hard keeps it where it is and Good/Easy move it up a box.
+    fn grade(&self, schedule: &mut Schedule, grade: Grade, now: DateTime<Utc>) {
+        let last_box = self.intervals.len().max(1);
+        schedule.leitner_box = match grade {
+            Grade::Again => 1,
+            Grade::Hard => schedule.leitner_box,
+            Grade::Good | Grade::Easy => schedule.leitner_box + 1,
+        }
+        .clamp(1, last_box);
+        if grade == Grade::Again {
+            schedule.repetitions = 0;
+        } else {
+            schedule.repetitions += 1;
+        }
+        schedule.interval = self
+            .intervals
+            .get(schedule.leitner_box - 1)
+            .copied()
+            .unwrap_or(1);
+        schedule.due = Some(now + Duration::days(schedule.interval as i64));
+    }
+}
+
+pub const FSRS_DEFAULT_WEIGHTS: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461, 2.1072,
+    0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+const FSRS_DECAY: f64 = -0.5;
+const FSRS_FACTOR: f64 = 19.0 / 81.0;
+//Bounds keep the optimizer from wandering into weights that make the formulas blow up.
+const FSRS_WEIGHT_BOUNDS: [(f64, f64); 17] = [
+    (0.1, 100.0),
+    (0.1, 100.0),
+    (0.1, 100.0),
+    (0.1, 100.0),
+    (1.0, 10.0),
+    (0.1, 5.0),
+    (0.1, 5.0),
+    (0.0, 0.75),
+    (0.0, 4.0),
+    (0.0, 0.8),
+    (0.01, 3.0),
+    (0.5, 5.0),
+    (0.01, 0.2),
+    (0.01, 0.9),
+    (0.01, 3.0),
+    (0.0, 1.0),
+    (1.0, 6.0),
+];
+
+//FSRS-4.5, the same memory model Anki uses, with retention targeting.
+pub struct Fsrs {
+    weights: Vec<f64>,
+    desired_retention: f64,
+}
+
+impl Fsrs {
+    pub fn new(weights: Vec<f64>, desired_retention: f64) -> Self {
+        Fsrs {
+            weights,
+            desired_retention,
+        }
+    }
+
+    pub fn retrievability(elapsed_days: f64, stability: f64) -> f64 {
+        (1.0 + FSRS_FACTOR * elapsed_days / stability).powf(FSRS_DECAY)
+    }
+
+    fn initial_stability(&self, grade: Grade) -> f64 {
+        self.weights[grade.rating() as usize - 1].max(0.1)
+    }
+
+    fn initial_difficulty(&self, grade: Grade) -> f64 {
+        (self.weights[4] - (grade.rating() - 3.0) * self.weights[5]).clamp(1.0, 10.0)
+    }
+
+    fn next_difficulty(&self, difficulty: f64, grade: Grade) -> f64 {
+        let next = difficulty - self.weights[6] * (grade.rating() - 3.0);
+        (self.weights[7] * self.initial_difficulty(Grade::Good) + (1.0 - self.weights[7]) * next)
+            .clamp(1.0, 10.0)
+    }
+
+    fn recall_stability(&self, difficulty: f64, stability: f64, recall: f64, grade: Grade) -> f64 {
+        let hard_penalty = if grade == Grade::Hard {
+            self.weights[15]
+        } else {
+            1.0
+        };
+        let easy_bonus = if grade == Grade::Easy {
+            self.weights[16]
+        } else {
+            1.0
+        };
+        stability
+            * (self.weights[8].exp()
+                * (11.0 - difficulty)
+                * stability.powf(-self.weights[9])
+                * ((self.weights[10] * (1.0 - recall)).exp() - 1.0)
+                * hard_penalty
+                * easy_bonus
+                + 1.0)
+    }
+
+    fn forget_stability(&self, difficulty: f64, stability: f64, recall: f64) -> f64 {
+        (self.weights[11]
+            * difficulty.powf(-self.weights[12])
+            * ((stability + 1.0).powf(self.weights[13]) - 1.0)
+            * (self.weights[14] * (1.0 - recall)).exp())
+        .min(stability)
+    }
+
+    //Moves a (stability, difficulty) memory state forward by one review.
+    fn step(&self, state: Option<(f64, f64)>, elapsed_days: f64, grade: Grade) -> (f64, f64) {
+        match state {
+            None => (
+                self.initial_stability(grade),
+                self.initial_difficulty(grade),
+            ),
+            Some((stability, difficulty)) => {
+                let recall = Fsrs::retrievability(elapsed_days, stability);
+                let next_stability = if grade == Grade::Again {
+                    self.forget_stability(difficulty, stability, recall)
+                } else {
+                    self.recall_stability(difficulty, stability, recall, grade)
+                };
+                (
+                    next_stability.max(0.1),
+                    self.next_difficulty(difficulty, grade),
+                )
+            }
+        }
+    }
+
+    //Rebuilds the memory state from review history, used for cards last scheduled by another algorithm.
+    fn replay(&self, reviews: &[Review]) -> Option<(f64, f64)> {
+        let mut state = None;
+        let mut last_time: Option<DateTime<Utc>> = None;
+        for review in reviews {
+            let elapsed = last_time.map_or(0.0, |time| elapsed_days(time, review.time));
+            state = Some(self.step(state, elapsed, review.grade));
+            last_time = Some(review.time);
+        }
+        state
+    }
+
+    fn next_interval(&self, stability: f64) -> u32 {
+        let interval =
+            stability / FSRS_FACTOR * (self.desired_retention.powf(1.0 / FSRS_DECAY) - 1.0);
+        interval.round().clamp(1.0, 36500.0) as u32
+    }
+
+    //Binary cross entropy between predicted recall and what actually happened, averaged per review.
+    fn loss(&self, histories: &[&[Review]]) -> f64 {
+        let mut total = 0.0;
+        let mut count = 0;
+        for reviews in histories {
+            let mut state = None;
+            let mut last_time: Option<DateTime<Utc>> = None;
+            for review in reviews.iter() {
+                if let (Some((stability, _)), Some(time)) = (state, last_time) {
+                    let recall = Fsrs::retrievability(elapsed_days(time, review.time), stability)
+                        .clamp(0.0001, 0.9999);
+                    total -= if review.grade == Grade::Again {
+                        (1.0 - recall).ln()
+                    } else {
+                        recall.ln()
+                    };
+                    count += 1;
+                }
+                let elapsed = last_time.map_or(0.0, |time| elapsed_days(time, review.time));
+                state = Some(self.step(state, elapsed, review.grade));
+                last_time = Some(review.time);
+            }
+        }
+        if count == 0 {
+            return 0.0;
+        }
+        total / count as f64
+    }
+
+    //Fits the weights to the given review histories with plain gradient descent on the log loss.
+    pub fn optimize(&self, histories: &[Vec<Review>]) -> Result<Vec<f64>, String> {
+        //Only the first review of each day counts, matching how FSRS treats long-term memory.
+        let daily: Vec<Vec<Review>> = histories
+            .iter()
+            .map(|reviews| {
+                let mut kept: Vec<Review> = Vec::new();
+                for review in reviews {
+                    let day = review.time.date_naive();
+                    if !matches!(kept.last(), Some(last) if last.time.date_naive() == day) {
+                        kept.push(review.clone());
+                    }
+                }
+                kept
+            })
+            .collect();
+        let samples: usize = daily
+            .iter()
+            .map(|reviews| reviews.len().saturating_sub(1))
+            .sum();
+        if samples < 16 {
+            return Err(format!(
+                "Not enough review history to optimize, found {} repeat reviews and need at least 16.",
+                samples
+            ));
+        }
+        let histories: Vec<&[Review]> = daily.iter().map(|reviews| reviews.as_slice()).collect();
+        let mut model = Fsrs::new(self.weights.clone(), self.desired_retention);
+        let mut learning_rate = 0.05;
+        let mut best_loss = model.loss(&histories);
+        for _ in 0..200 {
+            let mut gradient = vec![0.0; model.weights.len()];
+            for (index, slope) in gradient.iter_mut().enumerate() {
+                let step = 1e-4 * model.weights[index].abs().max(1.0);
+                let mut shifted = Fsrs::new(model.weights.clone(), self.desired_retention);
+                shifted.weights[index] += step;
+                *slope = (shifted.loss(&histories) - best_loss) / step;
+            }
+            let candidate_weights: Vec<f64> = model
+                .weights
+                .iter()
+                .zip(gradient.iter())
+                .zip(FSRS_WEIGHT_BOUNDS.iter())
+                .map(|((weight, slope), (low, high))| {
+                    (weight - learning_rate * slope * weight.abs().max(1.0)).clamp(*low, *high)
+                })
+                .collect();
+            let candidate = Fsrs::new(candidate_weights, self.desired_retention);
+            let candidate_loss = candidate.loss(&histories);
+            if candidate_loss < best_loss {
+                model = candidate;
+                best_loss = candidate_loss;
+                learning_rate *= 1.2;
+            } else {
+                learning_rate *= 0.5;
+                if learning_rate < 1e-6 {
+                    break;
+                }
+            }
+        }
+        Ok(model.weights)
+    }
+}
+
+impl Scheduler for Fsrs {
+    //The card you are most likely to have forgotten comes first, new cards go last.
+    fn next(&self, schedules: &[&Schedule], now: DateTime<Utc>) -> Option<usize> {
+        let recall = |schedule: &Schedule| match schedule.last_review() {
+            Some(time) if schedule.stability > 0.0 => {
+                Fsrs::retrievability(elapsed_days(time, now), schedule.stability)
+            }
+            _ => 1.0,
+        };
+        schedules
+            .iter()
+            .enumerate()
+            .filter(|(_, schedule)| schedule.is_due(now))
+            .min_by(|(_, a), (_, b)| {
+                a.is_new()
+                    .cmp(&b.is_new())
+                    .then(recall(a).total_cmp(&recall(b)))
+            })
+            .map(|(index, _)| index)
+    }
+
+    fn grade(&self, schedule: &mut Schedule, grade: Grade, now: DateTime<Utc>) {
+        let state = if schedule.stability > 0.0 {
+            Some((schedule.stability, schedule.difficulty))
+        } else {
+            self.replay(&schedule.reviews)
+        };
+        let elapsed = schedule
+            .last_review()
+            .map_or(0.0, |time| elapsed_days(time, now));
+        let (stability, difficulty) = self.step(state, elapsed, grade);
+        schedule.stability = stability;
+        schedule.difficulty = difficulty;
+        if grade == Grade::Again {
+            schedule.repetitions = 0;
+        } else {
+            schedule.repetitions += 1;
+        }
+        schedule.interval = self.next_interval(stability);
+        schedule.due = Some(now + Duration::days(schedule.interval as i64));
+    }
+}
+
+fn elapsed_days(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    ((to - from).num_seconds() as f64 / 86400.0).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::start;
+
+    //Grades every review on its due date and returns the (interval, ease) after each one.
+    fn run(scheduler: &dyn Scheduler, grades: &[Grade]) -> Vec<(u32, f64)> {
+        let mut schedule = Schedule::default();
+        let mut now = start();
+        grades
+            .iter()
+            .map(|grade| {
+                scheduler.grade(&mut schedule, *grade, now);
+                schedule.reviews.push(Review {
+                    time: now,
+                    grade: *grade,
+                });
+                now = schedule.due.unwrap();
+                (schedule.interval, schedule.ease_factor)
+            })
+            .collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn sm2_intervals_and_ease() {
+        let steps = run(
+            &Sm2,
+            &[
+                Grade::Good,
+                Grade::Good,
+                Grade::Good,
+                Grade::Easy,
+                Grade::Hard,
+                Grade::Again,
+                Grade::Good,
+            ],
+        );
+        let expected = [
+            (1, 2.5),
+            (6, 2.5),
+            (15, 2.5),
+            (38, 2.6),
+            (99, 2.46),
+            (1, 1.92),
+            (1, 1.92),
+        ];
+        assert_eq!(steps.len(), expected.len());
+        for ((interval, ease), (expected_interval, expected_ease)) in steps.iter().zip(expected) {
+            assert_eq!(*interval, expected_interval);
+            assert_close(*ease, expected_ease);
+        }
+    }
+
+    #[test]
+    fn sm2_ease_never_drops_below_minimum() {
+        let steps = run(&Sm2, &[Grade::Again; 6]);
+        assert_close(steps.last().unwrap().1, 1.3);
+        assert!(steps.iter().all(|(interval, _)| *interval == 1));
+    }
+
+    #[test]
+    fn sm2_due_date_follows_interval() {
+        let mut schedule = Schedule::default();
+        Sm2.grade(&mut schedule, Grade::Good, start());
+        Sm2.grade(&mut schedule, Grade::Good, start());
+        assert_eq!(schedule.due, Some(start() + Duration::days(6)));
+        assert!(!schedule.is_due(start() + Duration::days(5)));
+        assert!(schedule.is_due(start() + Duration::days(6)));
+    }
+
+    #[test]
+    fn sm2_picks_oldest_due_card_before_new_ones() {
+        let new = Schedule::default();
+        let due_later = Schedule {
+            due: Some(start() - Duration::days(1)),
+            ..Schedule::default()
+        };
+        let due_earlier = Schedule {
+            due: Some(start() - Duration::days(3)),
+            ..Schedule::default()
+        };
+        let not_due = Schedule {
+            due: Some(start() + Duration::days(1)),
+            ..Schedule::default()
+        };
+        assert_eq!(
+            Sm2.next(&[&new, &due_later, &not_due, &due_earlier], start()),
+            Some(3)
+        );
+        assert_eq!(Sm2.next(&[&not_due, &new], start()), Some(1));
+        assert_eq!(Sm2.next(&[&not_due], start()), None);
+    }
+
+    fn fsrs() -> Fsrs {
+        Fsrs::new(FSRS_DEFAULT_WEIGHTS.to_vec(), 0.9)
+    }
+
+    #[test]
+    fn fsrs_first_review_sets_initial_state() {
+        let fsrs = fsrs();
+        let expected = [
+            (Grade::Again, 0.4872, 7.6214),
+            (Grade::Hard, 1.4003, 6.3916),
+            (Grade::Good, 3.7145, 5.1618),
+            (Grade::Easy, 13.8206, 3.932),
+        ];
+        for (grade, stability, difficulty) in expected {
+            let mut schedule = Schedule::default();
+            fsrs.grade(&mut schedule, grade, start());
+            assert_close(schedule.stability, stability);
+            assert_close(schedule.difficulty, difficulty);
+        }
+    }
+
+    #[test]
+    fn fsrs_interval_matches_stability_at_ninety_percent() {
+        assert_close(Fsrs::retrievability(3.7145, 3.7145), 0.9);
+        assert_eq!(fsrs().next_interval(3.7145), 4);
+        assert_eq!(
+            Fsrs::new(FSRS_DEFAULT_WEIGHTS.to_vec(), 0.8).next_interval(3.7145),
+            9
+        );
+    }
+
+    #[test]
+    fn fsrs_stability_and_difficulty_updates() {
+        let fsrs = fsrs();
+        let mut schedule = Schedule::default();
+        let mut now = start();
+        let expected = [
+            (Grade::Good, 3.7145, 5.1618, 4),
+            (Grade::Good, 14.8081, 5.1618, 15),
+            (Grade::Again, 3.1493, 6.9012, 3),
+        ];
+        for (grade, stability, difficulty, interval) in expected {
+            fsrs.grade(&mut schedule, grade, now);
+            schedule.reviews.push(Review { time: now, grade });
+            assert_close(schedule.stability, stability);
+            assert_close(schedule.difficulty, difficulty);
+            assert_eq!(schedule.interval, interval);
+            now = schedule.due.unwrap();
+        }
+    }
+
+    #[test]
+    fn fsrs_hard_and_easy_recall() {
+        let fsrs = fsrs();
+        let state = Some((3.7145, 5.1618));
+        let (stability, difficulty) = fsrs.step(state, 4.0, Grade::Hard);
+        assert_close(stability, 6.2350);
+        assert_close(difficulty, 6.0315);
+        let (stability, difficulty) = fsrs.step(state, 4.0, Grade::Easy);
+        assert_close(stability, 35.6141);
+        assert_close(difficulty, 4.2921);
+    }
+
+    #[test]
+    fn fsrs_replays_history_from_other_schedulers() {
+        let fsrs = fsrs();
+        let mut schedule = Schedule::default();
+        let mut now = start();
+        for grade in [Grade::Good, Grade::Good] {
+            Sm2.grade(&mut schedule, grade, now);
+            schedule.reviews.push(Review { time: now, grade });
+            now += Duration::days(4);
+        }
+        fsrs.grade(&mut schedule, Grade::Good, start() + Duration::days(8));
+        let replayed = fsrs.replay(&schedule.reviews).unwrap();
+        let (stability, difficulty) = fsrs.step(Some(replayed), 4.0, Grade::Good);
+        assert_close(schedule.stability, stability);
+        assert_close(schedule.difficulty, difficulty);
+    }
+
+    //Cards reviewed a day, three days and a week apart, with every fourth card forgotten at the end.
+    fn histories(cards: usize) -> Vec<Vec<Review>> {
+        (0..cards)
+            .map(|card| {
+                [0, 1, 4, 11]
+                    .iter()
+                    .enumerate()
+                    .map(|(index, day)| Review {
+                        time: start() + Duration::days(*day),
+                        grade: if index == 3 && card % 4 == 0 {
+                            Grade::Again
+                        } else {
+                            Grade::Good
+                        },
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fsrs_optimizer_needs_enough_history() {
+        assert!(fsrs().optimize(&histories(5)).is_err());
+    }
+
+    #[test]
+    fn fsrs_optimizer_lowers_loss_within_bounds() {
+        let fsrs = fsrs();
+        let histories = histories(12);
+        let weights = fsrs.optimize(&histories).unwrap();
+        assert_eq!(weights.len(), FSRS_DEFAULT_WEIGHTS.len());
+        for (weight, (low, high)) in weights.iter().zip(FSRS_WEIGHT_BOUNDS) {
+            assert!(*weight >= low && *weight <= high);
+        }
+        let slices: Vec<&[Review]> = histories.iter().map(|reviews| reviews.as_slice()).collect();
+        assert!(Fsrs::new(weights, 0.9).loss(&slices) < fsrs.loss(&slices));
+    }
+
+    #[test]
+    fn leitner_moves_cards_between_boxes() {
+        let leitner = Leitner::new(LEITNER_DEFAULT_INTERVALS.to_vec());
+        let mut schedule = Schedule::default();
+        let grades = [
+            Grade::Good,
+            Grade::Good,
+            Grade::Hard,
+            Grade::Easy,
+            Grade::Good,
+            Grade::Good,
+            Grade::Again,
+        ];
+        let expected = [(2, 2), (3, 4), (3, 4), (4, 8), (5, 16), (5, 16), (1, 1)];
+        for (grade, (leitner_box, interval)) in grades.into_iter().zip(expected) {
+            leitner.grade(&mut schedule, grade, start());
+            assert_eq!(
+                (schedule.leitner_box, schedule.interval),
+                (leitner_box, interval)
+            );
+        }
+        assert_eq!(schedule.repetitions, 0);
+        assert_eq!(schedule.due, Some(start() + Duration::days(1)));
+    }
+
+    #[test]
+    fn leitner_studies_lower_boxes_first() {
+        let leitner = Leitner::new(LEITNER_DEFAULT_INTERVALS.to_vec());
+        let high = Schedule {
+            due: Some(start() - Duration::days(5)),
+            leitner_box: 4,
+            ..Schedule::default()
+        };
+        let low = Schedule {
+            due: Some(start() - Duration::days(1)),
+            leitner_box: 2,
+            ..Schedule::default()
+        };
+        assert_eq!(
+            leitner.next(&[&Schedule::default(), &high, &low], start()),
+            Some(2)
+        );
+    }
+
+    fn learning_steps() -> LearningSteps {
+        LearningSteps::new(
+            vec![Duration::minutes(1), Duration::minutes(10)],
+            vec![Duration::minutes(10)],
+        )
+    }
+
+    #[test]
+    fn learning_steps_before_graduating() {
+        let steps = learning_steps();
+        let mut schedule = Schedule::default();
+        let expected = [
+            (Grade::Good, Some(1), Duration::minutes(10)),
+            (Grade::Again, Some(0), Duration::minutes(1)),
+            (Grade::Hard, Some(0), Duration::minutes(1)),
+            (Grade::Good, Some(1), Duration::minutes(10)),
+            (Grade::Good, None, Duration::days(1)),
+        ];
+        for (grade, step, wait) in expected {
+            steps.review(&Sm2, &mut schedule, grade, start());
+            assert_eq!(schedule.learning_step, step);
+            assert_eq!(schedule.due, Some(start() + wait));
+        }
+        assert_eq!(schedule.state(), CardState::Review);
+        assert_eq!((schedule.interval, schedule.repetitions), (1, 1));
+        assert_eq!(schedule.reviews.len(), 5);
+    }
+
+    #[test]
+    fn learning_steps_easy_graduates_at_once() {
+        let mut schedule = Schedule::default();
+        learning_steps().review(&Sm2, &mut schedule, Grade::Easy, start());
+        assert_eq!(schedule.learning_step, None);
+        assert_eq!(schedule.due, Some(start() + Duration::days(1)));
+    }
+
+    #[test]
+    fn learning_steps_relearn_a_lapse() {
+        let steps = learning_steps();
+        let mut schedule = Schedule {
+            ease_factor: 2.5,
+            interval: 15,
+            repetitions: 3,
+            due: Some(start()),
+            ..Schedule::default()
+        };
+        steps.review(&Sm2, &mut schedule, Grade::Again, start());
+        assert_eq!(schedule.lapses, 1);
+        assert_eq!(schedule.state(), CardState::Learning);
+        assert_eq!(schedule.due, Some(start() + Duration::minutes(10)));
+        assert_close(schedule.ease_factor, 1.96);
+        steps.review(&Sm2, &mut schedule, Grade::Good, start());
+        assert_eq!(schedule.learning_step, None);
+        assert_eq!(schedule.interval, 1);
+        assert_close(schedule.ease_factor, 1.96);
+        assert_eq!(schedule.due, Some(start() + Duration::days(1)));
+    }
+}
@@ -1,16 +1,19 @@
 use std::{
     cell::RefCell,
-    cmp::min,
     error::Error,
     fs,
     io::{stdout, Stdout},
     path::PathBuf,
     rc::Rc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+use chrono::{DateTime, Local, Utc};
 use crossterm::{
-    event::{poll, read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{
+        poll, read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers,
+        MouseButton, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -21,37 +24,105 @@ use tui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Tabs},
+    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph, Tabs, Wrap},
     Terminal,
 };
 
-use crate::{card::Card, deck::Deck, util};
+use crate::{
+    answer::{self, Diff},
+    card::{Card, Variant},
+    deck::Deck,
+    exam::{Exam, ExamReport},
+    filter::Filter,
+    matching::{Column, MatchGame, MatchingLog},
+    quiz::{Question, QUIZ_CHOICES},
+    review_log::{ReviewLog, ReviewRecord},
+    scheduler::Grade,
+    session::{AnswerStyle, SavedSession, Session, StudyMode, StudyOption},
+    timer::{FocusLog, StudyTimer, TimerSettings},
+    util,
+};
 #[derive(Clone)]
 pub enum ScreenState {
     LocalMenu,
     DeckViewer,
     DeckEditor,
+    SessionSummary,
+    Exam,
+    ExamReport,
+    Matching,
+    Break,
+    TagBrowser,
 }
 
 #[derive(Clone)]
 pub enum EditMode {
     EditMenu(Rc<RefCell<ListState>>),
+    StudyMenu(Rc<RefCell<ListState>>),
     AddItem,
+    //Naming a new filtered deck, then writing its search query.
+    AddFilter,
+    EditFilter,
+    EditTags,
+    //Asking whether to pick up a session on the deck that was left at the given time.
+    ResumePrompt(StudyOption, DateTime<Utc>),
     EditContent,
     EditTitle,
+    TypeAnswer,
+    ChooseAnswer(Rc<RefCell<ListState>>),
     None,
 }
 
 pub struct ScreenOptions {
-    local_directory: PathBuf,
+    pub local_directory: PathBuf,
+    pub config_directory: PathBuf,
+    pub timer: TimerSettings,
 }
 
 impl ScreenOptions {
-    pub fn new(local_path: &str) -> Self {
+    pub fn new(local_path: &str, config_directory: PathBuf) -> Self {
         return ScreenOptions {
             local_directory: PathBuf::from(local_path),
+            config_directory,
+            timer: TimerSettings::default(),
         };
     }
+
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        if let Some(mut config_dir) = home_dir() {
+            config_dir.push(".flashrust");
+            let flash_dir = config_dir.clone();
+            let mut local_dir = config_dir.clone();
+            config_dir.push("config.ini");
+            if let Ok(_) = fs::create_dir_all(&flash_dir) {
+                local_dir.push("decks");
+                local_dir.push("local");
+                fs::create_dir_all(&local_dir)?;
+                //An existing config is left as it is, only a missing local directory gets filled in.
+                let mut config = Ini::load_from_file(&config_dir).unwrap_or_default();
+                if config
+                    .section(Some("Setup"))
+                    .and_then(|setup| setup.get("local_dir"))
+                    .is_none()
+                {
+                    config
+                        .with_section(Some("Setup"))
+                        .set("local_dir", local_dir.as_os_str().to_str().unwrap());
+                    config.write_to_file(&config_dir)?;
+                }
+            }
+            let config = Ini::load_from_file(config_dir)?;
+            if let Some(setup) = config.section(Some("Setup")) {
+                if let Some(local_path) = setup.get("local_dir") {
+                    let mut options = ScreenOptions::new(local_path, flash_dir);
+                    options.timer = TimerSettings::read(&config);
+                    return Ok(options);
+                }
+            }
+            return Err("Failed to read config file.")?;
+        }
+        return Err("Home directory not set, please make a home directory.")?;
+    }
 }
 
 pub struct Screen {
@@ -64,6 +135,29 @@ pub struct Screen {
     edit_failed: bool,
     right_panel_text_field: Rc<String>,
     options: Rc<ScreenOptions>,
+    session: Rc<RefCell<Session>>,
+    card_shown_at: Instant,
+    answer_diff: Rc<Option<Vec<Diff>>>,
+    question: Rc<Option<Question>>,
+    exam: Rc<RefCell<Exam>>,
+    exam_choice_state: Rc<RefCell<ListState>>,
+    //The marked exam and where its report was written, or why it couldn't be.
+    exam_report: Rc<Option<(ExamReport, Result<PathBuf, String>)>>,
+    matching: Rc<RefCell<MatchGame>>,
+    matching_cursor: Rc<RefCell<(Column, usize)>>,
+    //Where the two columns were last drawn, so mouse clicks can be mapped back to entries.
+    matching_areas: Rc<RefCell<[Rect; 2]>>,
+    //Which pair is on each line of the two columns, as they were last drawn.
+    matching_rows: Rc<RefCell<[Vec<usize>; 2]>>,
+    //Decks marked in the local menu to be studied together.
+    marked_decks: Rc<RefCell<Vec<String>>>,
+    timer: Rc<RefCell<StudyTimer>>,
+    //Every tag across the local decks with the titles of its cards, and the tag being studied or edited.
+    tags: Rc<Vec<(String, Vec<String>)>>,
+    tag_state: Rc<RefCell<ListState>>,
+    browsing_tag: Rc<Option<String>>,
+    //A save that failed, shown in the footer until the next key press.
+    error_message: Rc<Option<String>>,
 }
 
 impl Screen {
@@ -72,41 +166,36 @@ impl Screen {
         local_list_state.select(Some(0));
         let mut edit_list_state = ListState::default();
         edit_list_state.select(Some(0));
-        if let Some(mut config_dir) = home_dir() {
-            config_dir.push(".flashrust");
-            let mut local_dir = config_dir.clone();
-            if let Ok(_) = fs::create_dir_all(&config_dir) {
-                let mut config = Ini::new();
-                local_dir.push("decks");
-                local_dir.push("local");
-                fs::create_dir_all(&local_dir)?;
-                config
-                    .with_section(Some("Setup"))
-                    .set("local_dir", local_dir.as_os_str().to_str().unwrap());
-                config_dir.push("config.ini");
-                config.write_to_file(&config_dir)?;
-            }
-            let config = Ini::load_from_file(config_dir)?;
-            if let Some(setup) = config.section(Some("Setup")) {
-                if let Some(local_path) = setup.get("local_dir") {
-                    let screen_options = ScreenOptions::new(local_path);
-                    return Ok(Screen {
-                        state: Rc::new(state),
-                        local_menu_state: Rc::new(RefCell::new(local_list_state)),
-                        edit_menu_state: Rc::new(RefCell::new(edit_list_state)),
-                        local_decks_names: Screen::get_current_local_decks(&screen_options)
-                            .into_boxed_slice(),
-                        current_deck: Rc::new(RefCell::new(Deck::default())),
-                        edit_mode: Rc::new(EditMode::None),
-                        edit_failed: false,
-                        right_panel_text_field: Rc::new(String::default()),
-                        options: Rc::new(screen_options),
-                    });
-                }
-            }
-            return Err("Failed to read config file.")?;
-        }
-        return Err("Home directory not set, please make a home directory.")?;
+        let screen_options = ScreenOptions::load()?;
+        let study_timer = StudyTimer::new(screen_options.timer.clone());
+        return Ok(Screen {
+            state: Rc::new(state),
+            local_menu_state: Rc::new(RefCell::new(local_list_state)),
+            edit_menu_state: Rc::new(RefCell::new(edit_list_state)),
+            local_decks_names: Screen::get_current_local_decks(&screen_options).into_boxed_slice(),
+            current_deck: Rc::new(RefCell::new(Deck::default())),
+            edit_mode: Rc::new(EditMode::None),
+            edit_failed: false,
+            right_panel_text_field: Rc::new(String::default()),
+            options: Rc::new(screen_options),
+            session: Rc::new(RefCell::new(Session::default())),
+            card_shown_at: Instant::now(),
+            answer_diff: Rc::new(None),
+            question: Rc::new(None),
+            exam: Rc::new(RefCell::new(Exam::default())),
+            exam_choice_state: Rc::new(RefCell::new(ListState::default())),
+            exam_report: Rc::new(None),
+            matching: Rc::new(RefCell::new(MatchGame::default())),
+            matching_cursor: Rc::new(RefCell::new((Column::Fronts, 0))),
+            matching_areas: Rc::new(RefCell::new([Rect::default(); 2])),
+            matching_rows: Rc::new(RefCell::new([Vec::new(), Vec::new()])),
+            marked_decks: Rc::new(RefCell::new(Vec::new())),
+            timer: Rc::new(RefCell::new(study_timer)),
+            tags: Rc::new(Vec::new()),
+            tag_state: Rc::new(RefCell::new(ListState::default())),
+            browsing_tag: Rc::new(None),
+            error_message: Rc::new(None),
+        });
     }
 
     pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -120,10 +209,47 @@ impl Screen {
         loop {
             let initial_state = self.state.clone();
             if poll(Duration::from_millis(200))? {
-                if let Event::Key(key) = read()? {
+                let event = read()?;
+                if let Event::Mouse(mouse) = event {
+                    if let (ScreenState::Matching, MouseEventKind::Down(MouseButton::Left)) =
+                        (&*initial_state, mouse.kind)
+                    {
+                        let areas = *self.matching_areas.borrow();
+                        let rows = self.matching_rows.borrow().clone();
+                        for ((area, column), rows) in
+                            areas.iter().zip([Column::Fronts, Column::Backs]).zip(rows)
+                        {
+                            //The first and last rows of an area are its border.
+                            if mouse.column >= area.x
+                                && mouse.column < area.x + area.width
+                                && mouse.row > area.y
+                                && mouse.row + 1 < area.y + area.height
+                            {
+                                //The lists scroll, so the line is looked up rather than counted.
+                                if let Some(&row) = rows.get((mouse.row - area.y - 1) as usize) {
+                                    *self.matching_cursor.borrow_mut() = (column, row);
+                                    self.select_match(column, row)?;
+                                }
+                            }
+                        }
+                    }
+                }
+                if let Event::Key(key) = event {
+                    self.error_message = Rc::new(None);
                     match (*self.edit_mode).clone() {
                         EditMode::None => match key.code {
                             KeyCode::Char('e') => match *initial_state {
+                                ScreenState::TagBrowser => {
+                                    if let Some(tag) = self.selected_tag() {
+                                        let deck = self.read_tagged_deck(&tag);
+                                        self.browsing_tag = Rc::new(Some(tag));
+                                        let mut state = ListState::default();
+                                        state.select(Some(0));
+                                        self.edit_menu_state = Rc::new(RefCell::new(state));
+                                        self.current_deck = Rc::new(RefCell::new(deck));
+                                        self.state = Rc::new(ScreenState::DeckEditor);
+                                    }
+                                }
                                 ScreenState::LocalMenu => {
                                     if self.local_menu_state.borrow().selected().unwrap()
                                         == self.local_decks_names.len() - 1
@@ -148,6 +274,38 @@ impl Screen {
                                 }
                                 _ => (),
                             },
+                            KeyCode::Char('s') => match *initial_state {
+                                ScreenState::TagBrowser => self.open_tag_study_menu(),
+                                ScreenState::LocalMenu => {
+                                    if self.local_menu_state.borrow().selected().unwrap()
+                                        == self.local_decks_names.len() - 1
+                                    {
+                                        continue;
+                                    }
+                                    let mut state = ListState::default();
+                                    state.select(Some(0));
+                                    self.edit_mode =
+                                        Rc::new(EditMode::StudyMenu(Rc::new(RefCell::new(state))));
+                                }
+                                ScreenState::DeckEditor => {
+                                    let current_card =
+                                        self.edit_menu_state.borrow().selected().unwrap();
+                                    if current_card == self.current_deck.borrow().len() {
+                                        continue;
+                                    }
+                                    let mut deck = self.current_deck.borrow_mut();
+                                    let card = &mut deck.contents[current_card];
+                                    //Unsuspending a leech means it was rewritten, so it gets a clean slate.
+                                    if card.suspended && card.leech {
+                                        card.leech = false;
+                                        for schedule in card.schedules_mut() {
+                                            schedule.lapses = 0;
+                                        }
+                                    }
+                                    card.suspended = !card.suspended;
+                                }
+                                _ => (),
+                            },
                             KeyCode::Char('d') => match *initial_state {
                                 ScreenState::LocalMenu => {
                                     let current_deck =
@@ -159,6 +317,9 @@ impl Screen {
                                     let mut deck_path = self.options.local_directory.clone();
                                     deck_path.push(&temp_vec[current_deck]);
                                     fs::remove_dir_all(deck_path)?;
+                                    self.marked_decks
+                                        .borrow_mut()
+                                        .retain(|name| *name != temp_vec[current_deck]);
                                     temp_vec.remove(current_deck);
                                     self.local_decks_names = temp_vec.into_boxed_slice();
                                 }
@@ -170,8 +331,10 @@ impl Screen {
                                     }
                                     let mut temp_vec =
                                         self.current_deck.borrow().contents.clone().into_vec();
-                                    let mut card_path = self.options.local_directory.clone();
-                                    card_path.push(&self.current_deck.borrow().deck_title);
+                                    let mut card_path = self.current_deck.borrow().card_directory(
+                                        current_card,
+                                        &self.options.local_directory,
+                                    );
                                     card_path.push(temp_vec[current_card].saved_name());
                                     fs::remove_file(card_path)?;
                                     temp_vec.remove(current_card);
@@ -180,6 +343,34 @@ impl Screen {
                                 }
                                 _ => (),
                             },
+                            KeyCode::Char('t') => match *initial_state {
+                                ScreenState::LocalMenu => self.open_tag_browser(),
+                                _ => (),
+                            },
+                            KeyCode::Char('f') => match *initial_state {
+                                ScreenState::LocalMenu => {
+                                    self.right_panel_text_field = Rc::new(String::default());
+                                    self.edit_mode = Rc::new(EditMode::AddFilter);
+                                }
+                                _ => (),
+                            },
+                            KeyCode::Char(' ') => match *initial_state {
+                                ScreenState::LocalMenu => {
+                                    let current_deck =
+                                        self.local_menu_state.borrow().selected().unwrap();
+                                    if current_deck == self.local_decks_names.len() - 1 {
+                                        continue;
+                                    }
+                                    let name = &self.local_decks_names[current_deck];
+                                    let mut marked = self.marked_decks.borrow_mut();
+                                    if let Some(position) = marked.iter().position(|x| x == name) {
+                                        marked.remove(position);
+                                    } else {
+                                        marked.push(name.clone());
+                                    }
+                                }
+                                _ => (),
+                            },
                             KeyCode::Char('q') => match *initial_state {
                                 ScreenState::DeckEditor => {
                                     self.current_deck
@@ -187,9 +378,24 @@ impl Screen {
                                         .write_to_dir(self.options.local_directory.clone())?;
                                     break;
                                 }
+                                ScreenState::DeckViewer | ScreenState::Break => {
+                                    if !self.saved(self.save_session()) {
+                                        continue;
+                                    }
+                                    break;
+                                }
                                 _ => break,
                             },
                             KeyCode::Down => match *initial_state {
+                                ScreenState::TagBrowser => {
+                                    let new_state = util::offset_state(
+                                        &self.tag_state.borrow(),
+                                        1,
+                                        true,
+                                        self.tags.len().saturating_sub(1),
+                                    );
+                                    self.tag_state = Rc::new(RefCell::new(new_state));
+                                }
                                 ScreenState::LocalMenu => {
                                     let new_state = util::offset_state(
                                         &self.local_menu_state.clone().borrow(),
@@ -199,7 +405,37 @@ impl Screen {
                                     );
                                     self.local_menu_state = Rc::new(RefCell::new(new_state));
                                 }
+                                ScreenState::Exam => {
+                                    let new_state = util::offset_state(
+                                        &self.exam_choice_state.clone().borrow(),
+                                        1,
+                                        true,
+                                        self.exam_choices() - 1,
+                                    );
+                                    self.exam_choice_state = Rc::new(RefCell::new(new_state));
+                                }
+                                ScreenState::Matching => {
+                                    let last = self.matching.borrow().len().saturating_sub(1);
+                                    let mut cursor = self.matching_cursor.borrow_mut();
+                                    cursor.1 = (cursor.1 + 1).min(last);
+                                }
                                 ScreenState::DeckEditor => {
+                                    //Shift moves the selected card itself, which sets the manual study order.
+                                    if key.modifiers.contains(KeyModifiers::SHIFT) {
+                                        let current_card =
+                                            self.edit_menu_state.borrow().selected().unwrap();
+                                        if current_card == self.current_deck.borrow().len() {
+                                            continue;
+                                        }
+                                        let moved = self
+                                            .current_deck
+                                            .borrow_mut()
+                                            .move_card(current_card, false);
+                                        if let Some(target) = moved {
+                                            self.edit_menu_state.borrow_mut().select(Some(target));
+                                        }
+                                        continue;
+                                    }
                                     let new_state = util::offset_state(
                                         &self.edit_menu_state.clone().borrow(),
                                         1,
@@ -211,6 +447,15 @@ impl Screen {
                                 _ => (),
                             },
                             KeyCode::Up => match *initial_state {
+                                ScreenState::TagBrowser => {
+                                    let new_state = util::offset_state(
+                                        &self.tag_state.borrow(),
+                                        1,
+                                        false,
+                                        self.tags.len().saturating_sub(1),
+                                    );
+                                    self.tag_state = Rc::new(RefCell::new(new_state));
+                                }
                                 ScreenState::LocalMenu => {
                                     let new_state = util::offset_state(
                                         &self.local_menu_state.clone().borrow(),
@@ -220,7 +465,36 @@ impl Screen {
                                     );
                                     self.local_menu_state = Rc::new(RefCell::new(new_state));
                                 }
+                                ScreenState::Exam => {
+                                    let new_state = util::offset_state(
+                                        &self.exam_choice_state.clone().borrow(),
+                                        1,
+                                        false,
+                                        self.exam_choices() - 1,
+                                    );
+                                    self.exam_choice_state = Rc::new(RefCell::new(new_state));
+                                }
+                                ScreenState::Matching => {
+                                    let mut cursor = self.matching_cursor.borrow_mut();
+                                    cursor.1 = cursor.1.saturating_sub(1);
+                                }
                                 ScreenState::DeckEditor => {
+                                    //Shift moves the selected card itself, which sets the manual study order.
+                                    if key.modifiers.contains(KeyModifiers::SHIFT) {
+                                        let current_card =
+                                            self.edit_menu_state.borrow().selected().unwrap();
+                                        if current_card == self.current_deck.borrow().len() {
+                                            continue;
+                                        }
+                                        let moved = self
+                                            .current_deck
+                                            .borrow_mut()
+                                            .move_card(current_card, true);
+                                        if let Some(target) = moved {
+                                            self.edit_menu_state.borrow_mut().select(Some(target));
+                                        }
+                                        continue;
+                                    }
                                     let new_state = util::offset_state(
                                         &self.edit_menu_state.clone().borrow(),
                                         1,
@@ -242,47 +516,163 @@ impl Screen {
                                     {
                                         self.edit_mode = Rc::new(EditMode::AddItem);
                                     } else {
-                                        let mut cur_dir: PathBuf =
-                                            self.options.local_directory.clone();
-                                        cur_dir.push(
-                                            &self.local_decks_names[self
-                                                .local_menu_state
-                                                .borrow()
-                                                .selected()
-                                                .unwrap_or_default()],
-                                        );
-                                        if let Ok(deck) = Deck::read_from_dir(&cur_dir) {
-                                            self.current_deck = Rc::new(RefCell::new(deck));
-                                            self.state = Rc::new(ScreenState::DeckViewer);
-                                        }
+                                        self.open_study_session(StudyOption::Review);
                                     }
                                 }
+                                //Cards opened by tag come from many decks, so new cards are added in a deck.
                                 ScreenState::DeckEditor => {
                                     if self.edit_menu_state.borrow().selected().unwrap_or_default()
                                         == self.current_deck.borrow().len()
+                                        && self.browsing_tag.is_none()
                                     {
                                         self.edit_mode = Rc::new(EditMode::AddItem);
                                     }
                                 }
+                                ScreenState::TagBrowser => self.open_tag_study_menu(),
+                                //An answered quiz question grades itself on the way to the next card.
+                                ScreenState::DeckViewer => {
+                                    let grade = match &*self.question {
+                                        Some(question) if question.is_answered() => {
+                                            if question.is_correct() {
+                                                Grade::Good
+                                            } else {
+                                                Grade::Again
+                                            }
+                                        }
+                                        _ => continue,
+                                    };
+                                    self.grade_current_card(grade, Utc::now())?;
+                                }
+                                ScreenState::Exam => {
+                                    if let Some(choice) = self.exam_choice_state.borrow().selected()
+                                    {
+                                        self.exam.borrow_mut().answer(choice);
+                                    }
+                                    self.show_exam_question();
+                                }
+                                ScreenState::Matching => {
+                                    let (column, row) = *self.matching_cursor.borrow();
+                                    self.select_match(column, row)?;
+                                }
+                                //Breaks can be cut short, the work period starts over either way.
+                                ScreenState::Break => {
+                                    self.timer.borrow_mut().break_until = None;
+                                    self.card_shown_at = Instant::now();
+                                    self.state = Rc::new(ScreenState::DeckViewer);
+                                }
+                                _ => (),
+                            },
+                            KeyCode::Char(grade_key @ '1'..='4') => match *initial_state {
+                                ScreenState::DeckViewer => {
+                                    let now = Utc::now();
+                                    if self.session.borrow().is_finished()
+                                        || self.question.is_some()
+                                        || !self.current_deck.borrow().is_revealed()
+                                    {
+                                        continue;
+                                    }
+                                    if let Some(grade) = Grade::from_key(grade_key) {
+                                        self.grade_current_card(grade, now)?;
+                                    }
+                                }
+                                ScreenState::Exam => {
+                                    self.exam
+                                        .borrow_mut()
+                                        .answer(grade_key as usize - '1' as usize);
+                                    self.show_exam_question();
+                                }
                                 _ => (),
                             },
+                            KeyCode::Left | KeyCode::Right
+                                if key.modifiers.contains(KeyModifiers::SHIFT)
+                                    && matches!(*initial_state, ScreenState::DeckViewer) =>
+                            {
+                                let width =
+                                    Screen::build_layout(&mut terminal.get_frame())[2].width;
+                                self.current_deck
+                                    .borrow_mut()
+                                    .scroll_code(key.code == KeyCode::Right, width);
+                            }
                             KeyCode::Right => match *initial_state {
                                 ScreenState::DeckViewer => {
-                                    (*self.current_deck).borrow_mut().increment_deck(true);
+                                    (*self.current_deck).borrow_mut().increment_deck(false);
+                                }
+                                ScreenState::Exam => {
+                                    self.exam.borrow_mut().move_to(true);
+                                    self.show_exam_question();
+                                }
+                                ScreenState::Matching => {
+                                    self.matching_cursor.borrow_mut().0 = Column::Backs;
                                 }
                                 _ => (),
                             },
                             KeyCode::Left => match *initial_state {
                                 ScreenState::DeckViewer => {
-                                    (*self.current_deck).borrow_mut().decrement_deck(true);
+                                    (*self.current_deck).borrow_mut().decrement_deck(false);
+                                }
+                                ScreenState::Exam => {
+                                    self.exam.borrow_mut().move_to(false);
+                                    self.show_exam_question();
+                                }
+                                ScreenState::Matching => {
+                                    self.matching_cursor.borrow_mut().0 = Column::Fronts;
+                                }
+                                _ => (),
+                            },
+                            KeyCode::Char('r') => match *initial_state {
+                                ScreenState::SessionSummary => {
+                                    let missed = self.session.borrow().missed.clone();
+                                    if missed.is_empty() {
+                                        continue;
+                                    }
+                                    let mut session = Session::restudy(missed);
+                                    session.answer_style = self.session.borrow().answer_style;
+                                    session.next(&mut self.current_deck.borrow_mut(), Utc::now());
+                                    self.session = Rc::new(RefCell::new(session));
+                                    self.show_current_card();
+                                    self.state = Rc::new(ScreenState::DeckViewer);
+                                }
+                                ScreenState::Matching => {
+                                    let game =
+                                        MatchGame::new(&self.current_deck.borrow(), Utc::now());
+                                    self.matching = Rc::new(RefCell::new(game));
+                                    self.matching_cursor =
+                                        Rc::new(RefCell::new((Column::Fronts, 0)));
                                 }
                                 _ => (),
                             },
                             KeyCode::Esc => match *initial_state {
-                                ScreenState::DeckViewer => {
+                                ScreenState::DeckViewer
+                                | ScreenState::SessionSummary
+                                | ScreenState::Break => {
                                     self.current_deck = Rc::new(RefCell::new(Deck::default()));
+                                    self.session = Rc::new(RefCell::new(Session::default()));
+                                    self.return_to_menu();
+                                }
+                                ScreenState::TagBrowser => {
+                                    self.tags = Rc::new(Vec::new());
+                                    self.browsing_tag = Rc::new(None);
                                     self.state = Rc::new(ScreenState::LocalMenu);
                                 }
+                                //Leaving an exam early hands it in as it stands.
+                                ScreenState::Exam => self.finish_exam(),
+                                ScreenState::ExamReport => {
+                                    self.current_deck = Rc::new(RefCell::new(Deck::default()));
+                                    self.exam = Rc::new(RefCell::new(Exam::default()));
+                                    self.exam_report = Rc::new(None);
+                                    self.return_to_menu();
+                                }
+                                ScreenState::Matching => {
+                                    self.current_deck = Rc::new(RefCell::new(Deck::default()));
+                                    self.matching = Rc::new(RefCell::new(MatchGame::default()));
+                                    self.return_to_menu();
+                                }
+                                ScreenState::DeckEditor if self.browsing_tag.is_some() => {
+                                    self.current_deck
+                                        .take()
+                                        .write_to_dir(self.options.local_directory.clone())?;
+                                    self.return_to_menu();
+                                }
                                 ScreenState::DeckEditor => {
                                     self.state = Rc::new(ScreenState::LocalMenu);
                                     let mut state = ListState::default();
@@ -309,7 +699,7 @@ impl Screen {
                                 }
                                 ScreenState::DeckEditor => {
                                     let new_state =
-                                        util::offset_state(&menu_state.borrow(), 1, false, 1);
+                                        util::offset_state(&menu_state.borrow(), 1, false, 2);
                                     self.edit_mode = Rc::new(EditMode::EditMenu(Rc::new(
                                         RefCell::new(new_state),
                                     )));
@@ -326,7 +716,7 @@ impl Screen {
                                 }
                                 ScreenState::DeckEditor => {
                                     let new_state =
-                                        util::offset_state(&menu_state.borrow(), 1, true, 1);
+                                        util::offset_state(&menu_state.borrow(), 1, true, 2);
                                     self.edit_mode = Rc::new(EditMode::EditMenu(Rc::new(
                                         RefCell::new(new_state),
                                     )));
@@ -368,7 +758,11 @@ impl Screen {
                                                     .selected()
                                                     .unwrap_or_default()],
                                             );
-                                            if let Ok(deck) = Deck::read_from_dir(&cur_dir) {
+                                            //A filtered deck has no cards of its own, editing it changes the query.
+                                            if let Some(filter) = Filter::read_from_dir(&cur_dir) {
+                                                self.right_panel_text_field = Rc::new(filter.query);
+                                                self.edit_mode = Rc::new(EditMode::EditFilter);
+                                            } else if let Ok(deck) = Deck::read_from_dir(&cur_dir) {
                                                 self.current_deck = Rc::new(RefCell::new(deck));
                                                 self.state = Rc::new(ScreenState::DeckEditor);
                                                 self.edit_mode = Rc::new(EditMode::None);
@@ -390,11 +784,37 @@ impl Screen {
                                                 );
                                             }
                                             self.edit_mode = Rc::new(EditMode::EditTitle);
+                                        } else if item_index == 2 {
+                                            if let Some(current_selection) =
+                                                self.edit_menu_state.borrow().selected()
+                                            {
+                                                self.right_panel_text_field = Rc::new(
+                                                    self.current_deck.borrow().contents
+                                                        [current_selection]
+                                                        .tags
+                                                        .join(" "),
+                                                );
+                                            }
+                                            self.edit_mode = Rc::new(EditMode::EditTags);
                                         } else {
                                             self.edit_mode = Rc::new(EditMode::EditContent);
                                             self.current_deck.borrow_mut().cur_card =
                                                 self.edit_menu_state.borrow().selected().unwrap();
                                             let current_card = self.current_deck.borrow().cur_card;
+                                            //Cards of a note type get a section for every field.
+                                            let field_count = self.current_deck.borrow().contents
+                                                [current_card]
+                                                .note
+                                                .as_ref()
+                                                .map(|note| note.fields.len())
+                                                .unwrap_or(0);
+                                            let mut deck = self.current_deck.borrow_mut();
+                                            let card = &mut deck.contents[current_card];
+                                            if card.sections.len() < field_count {
+                                                card.sections
+                                                    .resize(field_count, String::default());
+                                            }
+                                            drop(deck);
                                             let current_section =
                                                 self.current_deck.borrow().contents[current_card]
                                                     .current_section;
@@ -423,6 +843,40 @@ impl Screen {
                             },
                             _ => (),
                         },
+                        EditMode::StudyMenu(menu_state) => match key.code {
+                            KeyCode::Up => {
+                                let new_state = util::offset_state(
+                                    &menu_state.borrow(),
+                                    1,
+                                    false,
+                                    StudyOption::ALL.len() - 1,
+                                );
+                                self.edit_mode =
+                                    Rc::new(EditMode::StudyMenu(Rc::new(RefCell::new(new_state))));
+                            }
+                            KeyCode::Down => {
+                                let new_state = util::offset_state(
+                                    &menu_state.borrow(),
+                                    1,
+                                    true,
+                                    StudyOption::ALL.len() - 1,
+                                );
+                                self.edit_mode =
+                                    Rc::new(EditMode::StudyMenu(Rc::new(RefCell::new(new_state))));
+                            }
+                            KeyCode::Esc => {
+                                self.edit_mode = Rc::new(EditMode::None);
+                                terminal.clear()?;
+                            }
+                            KeyCode::Enter => {
+                                if let Some(item_index) = menu_state.borrow().selected() {
+                                    self.edit_mode = Rc::new(EditMode::None);
+                                    terminal.clear()?;
+                                    self.open_study_session(StudyOption::ALL[item_index]);
+                                }
+                            }
+                            _ => (),
+                        },
                         EditMode::AddItem => match key.code {
                             KeyCode::Char(typed_char) => match *initial_state {
                                 ScreenState::LocalMenu => {
@@ -468,44 +922,35 @@ impl Screen {
                                         terminal.clear()?;
                                     }
                                 }
+                                //Titles are only shown, cards are saved under their id so titles may repeat.
                                 ScreenState::DeckEditor => {
                                     if !self.right_panel_text_field.is_empty() {
-                                        if !self
-                                            .current_deck
-                                            .borrow()
-                                            .get_card_names()
-                                            .contains(&self.right_panel_text_field)
-                                        {
-                                            let mut temp_vec =
-                                                self.current_deck.borrow_mut().contents.to_vec();
-                                            temp_vec.push(Card::new(
-                                                self.right_panel_text_field.clone().to_string(),
-                                            ));
-                                            self.current_deck.borrow_mut().contents =
-                                                temp_vec.into_boxed_slice();
-                                            self.current_deck.take().write_to_dir(
-                                                self.options.local_directory.clone(),
-                                            )?;
-                                            let mut cur_dir: PathBuf =
-                                                self.options.local_directory.clone();
-                                            cur_dir.push(
-                                                &self.local_decks_names[self
-                                                    .local_menu_state
-                                                    .borrow()
-                                                    .selected()
-                                                    .unwrap_or_default()],
-                                            );
-                                            if let Ok(deck) = Deck::read_from_dir(&cur_dir) {
-                                                self.current_deck = Rc::new(RefCell::new(deck));
-                                            }
-
-                                            self.right_panel_text_field =
-                                                Rc::new(String::default());
-                                            self.edit_mode = Rc::new(EditMode::None);
-                                            terminal.clear()?;
-                                        } else {
-                                            self.edit_failed = true;
+                                        let mut temp_vec =
+                                            self.current_deck.borrow_mut().contents.to_vec();
+                                        temp_vec.push(Card::new(
+                                            self.right_panel_text_field.clone().to_string(),
+                                        ));
+                                        self.current_deck.borrow_mut().contents =
+                                            temp_vec.into_boxed_slice();
+                                        self.current_deck
+                                            .take()
+                                            .write_to_dir(self.options.local_directory.clone())?;
+                                        let mut cur_dir: PathBuf =
+                                            self.options.local_directory.clone();
+                                        cur_dir.push(
+                                            &self.local_decks_names[self
+                                                .local_menu_state
+                                                .borrow()
+                                                .selected()
+                                                .unwrap_or_default()],
+                                        );
+                                        if let Ok(deck) = Deck::read_from_dir(&cur_dir) {
+                                            self.current_deck = Rc::new(RefCell::new(deck));
                                         }
+
+                                        self.right_panel_text_field = Rc::new(String::default());
+                                        self.edit_mode = Rc::new(EditMode::None);
+                                        terminal.clear()?;
                                     } else {
                                         self.right_panel_text_field = Rc::new(String::default());
                                         self.edit_mode = Rc::new(EditMode::None);
@@ -542,27 +987,143 @@ impl Screen {
                             },
                             _ => (),
                         },
-                        EditMode::EditContent => match (key.code, key.modifiers) {
-                            (KeyCode::Char('a'), KeyModifiers::CONTROL) => match *initial_state {
-                                ScreenState::DeckEditor => {
-                                    let current_card = self.current_deck.borrow().cur_card;
-                                    let current_section = self.current_deck.borrow().contents
-                                        [current_card]
-                                        .current_section;
-                                    self.current_deck.borrow_mut().contents[current_card]
-                                        .sections[current_section] =
-                                        self.right_panel_text_field.clone().to_string();
-                                    self.current_deck.borrow_mut().contents[current_card]
-                                        .sections
-                                        .insert(current_section, String::from(""));
+                        EditMode::ResumePrompt(option, _) => match key.code {
+                            KeyCode::Char('y') | KeyCode::Enter => {
+                                self.edit_mode = Rc::new(EditMode::None);
+                                terminal.clear()?;
+                                self.resume_study_session(option);
+                            }
+                            KeyCode::Char('n') => {
+                                if let Ok(deck) = self.load_selected_decks() {
+                                    SavedSession::remove_from_dir(
+                                        &self.options.config_directory,
+                                        &deck.deck_title,
+                                    );
+                                }
+                                self.edit_mode = Rc::new(EditMode::None);
+                                terminal.clear()?;
+                                self.open_study_session(option);
+                            }
+                            KeyCode::Esc => {
+                                self.edit_mode = Rc::new(EditMode::None);
+                                terminal.clear()?;
+                            }
+                            _ => (),
+                        },
+                        EditMode::AddFilter | EditMode::EditFilter | EditMode::EditTags => {
+                            match key.code {
+                                KeyCode::Char(typed_char) => {
+                                    if self.edit_failed {
+                                        self.edit_failed = false;
+                                    }
+                                    let mut current_text = (*self.right_panel_text_field).clone();
+                                    current_text.push(typed_char);
+                                    self.right_panel_text_field = Rc::new(current_text);
+                                }
+                                KeyCode::Backspace => {
+                                    let mut current_text = (*self.right_panel_text_field).clone();
+                                    current_text.pop();
+                                    self.right_panel_text_field = Rc::new(current_text);
+                                }
+                                KeyCode::Enter if matches!(*self.edit_mode, EditMode::EditTags) => {
+                                    if let Some(current_card) =
+                                        self.edit_menu_state.borrow().selected()
+                                    {
+                                        let mut deck = self.current_deck.borrow_mut();
+                                        deck.contents[current_card]
+                                            .set_tags(&self.right_panel_text_field);
+                                        let card = deck.contents[current_card].clone();
+                                        card.write_to_file(deck.card_directory(
+                                            current_card,
+                                            &self.options.local_directory,
+                                        ))?;
+                                    }
                                     self.right_panel_text_field = Rc::new(String::default());
+                                    self.edit_mode = Rc::new(EditMode::None);
+                                    terminal.clear()?;
                                 }
-                                _ => (),
-                            },
-                            (KeyCode::Char('d'), KeyModifiers::CONTROL) => match *initial_state {
-                                ScreenState::DeckEditor => {
-                                    let current_card = self.current_deck.borrow().cur_card;
-                                    let current_section = self.current_deck.borrow().contents
+                                KeyCode::Enter
+                                    if matches!(*self.edit_mode, EditMode::AddFilter) =>
+                                {
+                                    if self.right_panel_text_field.is_empty() {
+                                        self.edit_mode = Rc::new(EditMode::None);
+                                        terminal.clear()?;
+                                        continue;
+                                    }
+                                    let name = self.right_panel_text_field.to_string();
+                                    let new_dir = self.options.local_directory.join(&name);
+                                    if fs::create_dir(&new_dir).is_err() {
+                                        self.edit_failed = true;
+                                        continue;
+                                    }
+                                    Filter::default().write_to_dir(&new_dir)?;
+                                    let mut temp_vec = self.local_decks_names.to_vec();
+                                    temp_vec.insert(temp_vec.len() - 1, name);
+                                    self.local_menu_state
+                                        .borrow_mut()
+                                        .select(Some(temp_vec.len() - 2));
+                                    self.local_decks_names = temp_vec.into_boxed_slice();
+                                    self.right_panel_text_field = Rc::new(String::default());
+                                    self.edit_mode = Rc::new(EditMode::EditFilter);
+                                }
+                                KeyCode::Enter => {
+                                    let mut cur_dir: PathBuf = self.options.local_directory.clone();
+                                    cur_dir.push(
+                                        &self.local_decks_names[self
+                                            .local_menu_state
+                                            .borrow()
+                                            .selected()
+                                            .unwrap_or_default()],
+                                    );
+                                    Filter::parse(&self.right_panel_text_field)
+                                        .write_to_dir(&cur_dir)?;
+                                    self.right_panel_text_field = Rc::new(String::default());
+                                    self.edit_mode = Rc::new(EditMode::None);
+                                    terminal.clear()?;
+                                }
+                                KeyCode::Esc => {
+                                    self.right_panel_text_field = Rc::new(String::default());
+                                    self.edit_failed = false;
+                                    self.edit_mode = Rc::new(EditMode::None);
+                                    terminal.clear()?;
+                                }
+                                _ => (),
+                            }
+                        }
+                        EditMode::EditContent => match (key.code, key.modifiers) {
+                            (KeyCode::Char('a'), KeyModifiers::CONTROL) => match *initial_state {
+                                ScreenState::DeckEditor => {
+                                    let current_card = self.current_deck.borrow().cur_card;
+                                    //The fields of a note type are fixed by the deck.
+                                    if self.current_deck.borrow().contents[current_card]
+                                        .note
+                                        .is_some()
+                                    {
+                                        continue;
+                                    }
+                                    let current_section = self.current_deck.borrow().contents
+                                        [current_card]
+                                        .current_section;
+                                    self.current_deck.borrow_mut().contents[current_card]
+                                        .sections[current_section] =
+                                        self.right_panel_text_field.clone().to_string();
+                                    self.current_deck.borrow_mut().contents[current_card]
+                                        .sections
+                                        .insert(current_section, String::from(""));
+                                    self.right_panel_text_field = Rc::new(String::default());
+                                }
+                                _ => (),
+                            },
+                            (KeyCode::Char('d'), KeyModifiers::CONTROL) => match *initial_state {
+                                ScreenState::DeckEditor => {
+                                    let current_card = self.current_deck.borrow().cur_card;
+                                    if self.current_deck.borrow().contents[current_card]
+                                        .note
+                                        .is_some()
+                                    {
+                                        continue;
+                                    }
+                                    let current_section = self.current_deck.borrow().contents
                                         [current_card]
                                         .current_section;
                                     if !self.current_deck.borrow().contents[current_card]
@@ -587,7 +1148,7 @@ impl Screen {
                                                 );
                                                 self.current_deck.borrow_mut().contents
                                                     [current_card]
-                                                    .decrement_section();
+                                                    .decrement_section(Variant::Forward);
                                             }
                                         } else {
                                             self.right_panel_text_field = Rc::new(
@@ -773,39 +1334,22 @@ impl Screen {
                                 }
                                 ScreenState::DeckEditor => {
                                     if !self.right_panel_text_field.is_empty() {
-                                        if self
-                                            .current_deck
-                                            .borrow()
-                                            .get_card_names()
-                                            .contains(&self.right_panel_text_field)
-                                        {
-                                            self.edit_failed = true;
-                                            continue;
-                                        }
-                                        let mut old_dir = self.options.local_directory.clone();
-                                        old_dir.push(&self.current_deck.borrow().deck_title);
-                                        old_dir.push(
-                                            self.current_deck.borrow().contents
-                                                [self.edit_menu_state.borrow().selected().unwrap()]
-                                            .saved_name(),
-                                        );
-                                        let mut new_dir = self.options.local_directory.clone();
-                                        new_dir.push(&self.current_deck.borrow().deck_title);
-                                        new_dir.push(
-                                            self.right_panel_text_field.to_string() + ".json",
+                                        //Cards are saved under their id, so renaming only rewrites the card.
+                                        let current_card =
+                                            self.edit_menu_state.borrow().selected().unwrap();
+                                        let deck_path = self.current_deck.borrow().card_directory(
+                                            current_card,
+                                            &self.options.local_directory,
                                         );
-                                        if let Ok(_) = fs::rename(old_dir, new_dir) {
-                                            self.current_deck.borrow_mut().contents[self
-                                                .edit_menu_state
-                                                .borrow()
-                                                .selected()
-                                                .unwrap()]
+                                        self.current_deck.borrow_mut().contents[current_card]
                                             .title = self.right_panel_text_field.to_string();
-                                            self.right_panel_text_field =
-                                                Rc::new(String::default());
-                                            self.edit_mode = Rc::new(EditMode::None);
-                                            terminal.clear()?;
-                                        }
+                                        let card = self.current_deck.borrow().contents
+                                            [current_card]
+                                            .clone();
+                                        card.write_to_file(deck_path)?;
+                                        self.right_panel_text_field = Rc::new(String::default());
+                                        self.edit_mode = Rc::new(EditMode::None);
+                                        terminal.clear()?;
                                     } else {
                                         self.right_panel_text_field = Rc::new(String::default());
                                         self.edit_mode = Rc::new(EditMode::None);
@@ -850,9 +1394,135 @@ impl Screen {
                             },
                             _ => (),
                         },
+                        EditMode::ChooseAnswer(choice_state) => match key.code {
+                            KeyCode::Up => {
+                                let new_state = util::offset_state(
+                                    &choice_state.borrow(),
+                                    1,
+                                    false,
+                                    self.question_choices() - 1,
+                                );
+                                self.edit_mode = Rc::new(EditMode::ChooseAnswer(Rc::new(
+                                    RefCell::new(new_state),
+                                )));
+                            }
+                            KeyCode::Down => {
+                                let new_state = util::offset_state(
+                                    &choice_state.borrow(),
+                                    1,
+                                    true,
+                                    self.question_choices() - 1,
+                                );
+                                self.edit_mode = Rc::new(EditMode::ChooseAnswer(Rc::new(
+                                    RefCell::new(new_state),
+                                )));
+                            }
+                            KeyCode::Enter => {
+                                if let Some(choice) = choice_state.borrow().selected() {
+                                    self.choose_answer(choice);
+                                }
+                            }
+                            KeyCode::Char(choice_key @ '1'..='9') => {
+                                let choice = choice_key as usize - '1' as usize;
+                                if choice < self.question_choices() {
+                                    self.choose_answer(choice);
+                                }
+                            }
+                            KeyCode::Char('q') => {
+                                if !self.saved(self.save_session()) {
+                                    continue;
+                                }
+                                break;
+                            }
+                            KeyCode::Esc => {
+                                self.current_deck = Rc::new(RefCell::new(Deck::default()));
+                                self.session = Rc::new(RefCell::new(Session::default()));
+                                self.question = Rc::new(None);
+                                self.edit_mode = Rc::new(EditMode::None);
+                                self.return_to_menu();
+                            }
+                            _ => (),
+                        },
+                        EditMode::TypeAnswer => match key.code {
+                            //Plain q is part of the answer, so quitting takes ctrl.
+                            KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                if !self.saved(self.save_session()) {
+                                    continue;
+                                }
+                                break;
+                            }
+                            KeyCode::Char(typed_char) => {
+                                let mut current_answer = (*self.right_panel_text_field).clone();
+                                current_answer.push(typed_char);
+                                self.right_panel_text_field = Rc::new(current_answer);
+                            }
+                            KeyCode::Backspace => {
+                                let mut current_answer = (*self.right_panel_text_field).clone();
+                                current_answer.pop();
+                                self.right_panel_text_field = Rc::new(current_answer);
+                            }
+                            //Checking the answer reveals the whole card so it can be graded.
+                            KeyCode::Enter => {
+                                let mut deck = self.current_deck.borrow_mut();
+                                let matching = deck.settings.answer_matching;
+                                let item = deck.current_item();
+                                self.answer_diff = Rc::new(Some(matching.diff(
+                                    &self.right_panel_text_field,
+                                    &deck.contents[item.card].answer(item.variant),
+                                )));
+                                deck.reveal();
+                                self.right_panel_text_field = Rc::new(String::default());
+                                self.edit_mode = Rc::new(EditMode::None);
+                            }
+                            KeyCode::Esc => {
+                                self.current_deck = Rc::new(RefCell::new(Deck::default()));
+                                self.session = Rc::new(RefCell::new(Session::default()));
+                                self.right_panel_text_field = Rc::new(String::default());
+                                self.edit_mode = Rc::new(EditMode::None);
+                                self.return_to_menu();
+                            }
+                            _ => (),
+                        },
                     }
                 }
             }
+            //Learning cards come back on their own once their step is up, even without a key press.
+            if let ScreenState::DeckViewer = *self.state {
+                let waiting = self.session.borrow().is_finished()
+                    && self.session.borrow().next_pending().is_some();
+                if waiting
+                    && self
+                        .session
+                        .borrow_mut()
+                        .next(&mut self.current_deck.borrow_mut(), Utc::now())
+                        .is_some()
+                {
+                    self.show_current_card();
+                }
+            }
+            //The study timer runs off the same poll, it only counts time spent with a card on screen.
+            let studying = matches!(*self.state, ScreenState::DeckViewer)
+                && !self.session.borrow().is_finished();
+            if self.timer.borrow_mut().tick(studying, Utc::now()) {
+                self.state = Rc::new(ScreenState::Break);
+                self.log_focus_time()?;
+                self.timer.borrow_mut().studied_today =
+                    FocusLog::new(&self.options.config_directory)
+                        .read()
+                        .get(&Local::now().date_naive())
+                        .copied()
+                        .unwrap_or_default();
+            }
+            if !studying {
+                self.log_focus_time()?;
+            }
+            //The poll above wakes the loop often enough to run the exam clock.
+            if let ScreenState::Exam = *self.state {
+                let time_up = self.exam.borrow().is_over(Utc::now());
+                if time_up {
+                    self.finish_exam();
+                }
+            }
             //First we find the areas of the screen we are drawing to, then we draw each part of the screen using the appropriate function.
             let menu_layout = Screen::build_layout(&mut terminal.get_frame());
             terminal.draw(|f| {
@@ -862,6 +1532,7 @@ impl Screen {
                 self.render_right_panel_content(f, &menu_layout[3]);
             })?;
         }
+        self.log_focus_time()?;
         disable_raw_mode()?;
         execute!(
             terminal.backend_mut(),
@@ -872,8 +1543,349 @@ impl Screen {
         Ok(())
     }
 
+    //Loads the decks marked in the local menu, or else the selected one and the decks in its folder,
+    //and starts studying them the chosen way.
+    fn open_study_session(&mut self, option: StudyOption) {
+        if let Ok(mut deck) = self.load_selected_decks() {
+            let now = Utc::now();
+            if option == StudyOption::Exam {
+                let exam = Exam::new(&deck, now);
+                if exam.questions.is_empty() {
+                    return;
+                }
+                self.current_deck = Rc::new(RefCell::new(deck));
+                self.exam = Rc::new(RefCell::new(exam));
+                self.show_exam_question();
+                self.state = Rc::new(ScreenState::Exam);
+                return;
+            }
+            if option == StudyOption::Matching {
+                let game = MatchGame::new(&deck, now);
+                if game.len() < 2 {
+                    return;
+                }
+                self.current_deck = Rc::new(RefCell::new(deck));
+                self.matching = Rc::new(RefCell::new(game));
+                self.matching_cursor = Rc::new(RefCell::new((Column::Fronts, 0)));
+                self.state = Rc::new(ScreenState::Matching);
+                return;
+            }
+            if let Some(saved) = SavedSession::read_from_dir(&self.options.config_directory, &deck)
+            {
+                self.edit_mode = Rc::new(EditMode::ResumePrompt(option, saved.saved));
+                return;
+            }
+            let mut session = Session::start(option, &deck, now);
+            session.next(&mut deck, now);
+            self.timer.borrow_mut().start_session();
+            self.session = Rc::new(RefCell::new(session));
+            self.current_deck = Rc::new(RefCell::new(deck));
+            self.show_current_card();
+            self.state = Rc::new(ScreenState::DeckViewer);
+        }
+    }
+
+    //Picks a session left with 'q' back up where it was, or starts afresh if it can't be read anymore.
+    fn resume_study_session(&mut self, option: StudyOption) {
+        if let Ok(mut deck) = self.load_selected_decks() {
+            match SavedSession::read_from_dir(&self.options.config_directory, &deck) {
+                Some(saved) => {
+                    SavedSession::remove_from_dir(&self.options.config_directory, &saved.deck);
+                    self.timer.borrow_mut().start_session();
+                    self.session = Rc::new(RefCell::new(saved.restore(&mut deck)));
+                    self.current_deck = Rc::new(RefCell::new(deck));
+                    self.show_current_card();
+                    self.state = Rc::new(ScreenState::DeckViewer);
+                }
+                None => self.open_study_session(option),
+            }
+        }
+    }
+
+    //Every card with the tag across all local decks, each one is still saved in its own deck.
+    fn read_tagged_deck(&self, tag: &str) -> Deck {
+        Deck::read_matching(
+            &self.options.local_directory,
+            &format!("#{}", tag),
+            &Filter::parse(&format!("tag:{}", tag)),
+            Utc::now(),
+        )
+    }
+
+    fn open_tag_browser(&mut self) {
+        let deck = Deck::read_matching(
+            &self.options.local_directory,
+            "",
+            &Filter::default(),
+            Utc::now(),
+        );
+        let tags = deck
+            .tag_counts()
+            .into_iter()
+            .map(|(tag, _)| {
+                let titles = deck
+                    .contents
+                    .iter()
+                    .filter(|card| card.tags.contains(&tag))
+                    .map(|card| card.title.clone())
+                    .collect();
+                (tag, titles)
+            })
+            .collect::<Vec<(String, Vec<String>)>>();
+        let mut state = ListState::default();
+        state.select(Some(
+            self.tag_state
+                .borrow()
+                .selected()
+                .unwrap_or_default()
+                .min(tags.len().saturating_sub(1)),
+        ));
+        self.tags = Rc::new(tags);
+        self.tag_state = Rc::new(RefCell::new(state));
+        self.state = Rc::new(ScreenState::TagBrowser);
+    }
+
+    fn selected_tag(&self) -> Option<String> {
+        let selected = self.tag_state.borrow().selected()?;
+        self.tags.get(selected).map(|(tag, _)| tag.clone())
+    }
+
+    fn open_tag_study_menu(&mut self) {
+        if let Some(tag) = self.selected_tag() {
+            self.browsing_tag = Rc::new(Some(tag));
+            let mut state = ListState::default();
+            state.select(Some(0));
+            self.edit_mode = Rc::new(EditMode::StudyMenu(Rc::new(RefCell::new(state))));
+        }
+    }
+
+    //Goes back to the tag browser when the deck was opened from it, otherwise to the local menu.
+    //Quitting mid session keeps it around to be resumed later, a finished one is dropped.
+    fn save_session(&self) -> Result<(), Box<dyn Error>> {
+        let deck = self.current_deck.borrow();
+        let session = self.session.borrow();
+        if session.is_finished() && session.next_pending().is_none() {
+            SavedSession::remove_from_dir(&self.options.config_directory, &deck.deck_title);
+        } else {
+            SavedSession::new(&deck, &session, Utc::now())
+                .write_to_dir(&self.options.config_directory)?;
+        }
+        Ok(())
+    }
+
+    //Keeps the screen up with the error in the footer when a save failed, rather than quitting without it.
+    fn saved(&mut self, result: Result<(), Box<dyn Error>>) -> bool {
+        if let Err(err) = &result {
+            self.error_message = Rc::new(Some(format!("Couldn't save the session: {}", err)));
+        }
+        result.is_ok()
+    }
+
+    fn return_to_menu(&mut self) {
+        if self.browsing_tag.is_some() {
+            self.open_tag_browser();
+        } else {
+            self.state = Rc::new(ScreenState::LocalMenu);
+        }
+    }
+
+    //Adds the time studied since the last call to today's total in the focus log.
+    fn log_focus_time(&mut self) -> Result<(), Box<dyn Error>> {
+        let unlogged = self.timer.borrow_mut().take_unlogged();
+        FocusLog::new(&self.options.config_directory).add(Local::now().date_naive(), unlogged)
+    }
+
+    fn load_selected_decks(&self) -> Result<Deck, String> {
+        if let Some(tag) = &*self.browsing_tag {
+            return Ok(self.read_tagged_deck(tag));
+        }
+        let marked = self.marked_decks.borrow().clone();
+        if marked.is_empty() {
+            let mut cur_dir: PathBuf = self.options.local_directory.clone();
+            cur_dir.push(
+                &self.local_decks_names[self
+                    .local_menu_state
+                    .borrow()
+                    .selected()
+                    .unwrap_or_default()],
+            );
+            Deck::read_tree(&cur_dir)
+        } else {
+            let decks = marked
+                .iter()
+                .filter_map(|name| Deck::read_tree(&self.options.local_directory.join(name)).ok())
+                .collect();
+            Ok(Deck::merge(&marked.join(" + "), decks))
+        }
+    }
+
+    //Puts the exam's current question on screen with its answer so far highlighted.
+    fn show_exam_question(&mut self) {
+        let exam = self.exam.borrow();
+        if let Some(item) = exam.current_item() {
+            self.current_deck.borrow_mut().show(item);
+        }
+        let chosen = exam.current_question().and_then(|question| question.chosen);
+        self.exam_choice_state
+            .borrow_mut()
+            .select(Some(chosen.unwrap_or_default()));
+    }
+
+    fn exam_choices(&self) -> usize {
+        self.exam
+            .borrow()
+            .current_question()
+            .map(|question| question.choices.len())
+            .unwrap_or(QUIZ_CHOICES)
+    }
+
+    //Picks a matching game entry and logs the game once the last pair is found.
+    fn select_match(&mut self, column: Column, row: usize) -> Result<(), Box<dyn Error>> {
+        let was_finished = self.matching.borrow().finished.is_some();
+        self.matching.borrow_mut().select(column, row, Utc::now());
+        let game = self.matching.borrow();
+        if !was_finished && game.finished.is_some() {
+            MatchingLog::new(&self.options.config_directory)
+                .append(&game.result(&self.current_deck.borrow(), Utc::now()))?;
+        }
+        Ok(())
+    }
+
+    //Marks the exam and writes the report next to the review log.
+    fn finish_exam(&mut self) {
+        let report = self
+            .exam
+            .borrow()
+            .report(&self.current_deck.borrow(), Utc::now());
+        let file_path = report
+            .write_to_dir(&self.options.config_directory)
+            .map_err(|err| err.to_string());
+        self.exam_report = Rc::new(Some((report, file_path)));
+        self.state = Rc::new(ScreenState::ExamReport);
+    }
+
+    //Starts the response timer for a freshly shown card and waits for an answer if the session asks for one.
+    fn show_current_card(&mut self) {
+        self.card_shown_at = Instant::now();
+        self.answer_diff = Rc::new(None);
+        self.question = Rc::new(None);
+        if let EditMode::TypeAnswer | EditMode::ChooseAnswer(_) = *self.edit_mode {
+            self.edit_mode = Rc::new(EditMode::None);
+        }
+        if self.session.borrow().is_finished() {
+            return;
+        }
+        match self.session.borrow().answer_style {
+            AnswerStyle::Flip => (),
+            AnswerStyle::Typed => {
+                self.right_panel_text_field = Rc::new(String::default());
+                self.edit_mode = Rc::new(EditMode::TypeAnswer);
+            }
+            //Cards without an answer section to quiz on are simply flipped.
+            AnswerStyle::MultipleChoice => {
+                let deck = self.current_deck.borrow();
+                if let Some(question) = Question::new(&deck, deck.current_item()) {
+                    let mut state = ListState::default();
+                    state.select(Some(0));
+                    self.question = Rc::new(Some(question));
+                    self.edit_mode = Rc::new(EditMode::ChooseAnswer(Rc::new(RefCell::new(state))));
+                }
+            }
+        }
+    }
+
+    fn question_choices(&self) -> usize {
+        self.question
+            .as_ref()
+            .as_ref()
+            .map(|question| question.choices.len())
+            .unwrap_or(QUIZ_CHOICES)
+    }
+
+    //Locks in a quiz answer and reveals the card, the grade follows from whether it was right.
+    fn choose_answer(&mut self, choice: usize) {
+        if let Some(mut question) = (*self.question).clone() {
+            question.chosen = Some(choice);
+            self.question = Rc::new(Some(question));
+        }
+        self.current_deck.borrow_mut().reveal();
+        self.edit_mode = Rc::new(EditMode::None);
+    }
+
+    //Schedules the card on screen, saves it, logs the answer and moves on to the next due card.
+    fn grade_current_card(
+        &mut self,
+        grade: Grade,
+        now: DateTime<Utc>,
+    ) -> Result<(), Box<dyn Error>> {
+        let item = self.current_deck.borrow().current_item();
+        //Cards of a merged deck are saved and logged under the deck they came from.
+        let (deck_title, deck_path) = match self.current_deck.borrow().source(item.card) {
+            Some(source) => (source.title.clone(), source.path.clone()),
+            None => {
+                let deck_title = self.current_deck.borrow().deck_title.clone();
+                let deck_path = self.options.local_directory.join(&deck_title);
+                (deck_title, deck_path)
+            }
+        };
+        let previous_interval = self.current_deck.borrow().schedule(item).interval;
+        let mode = self.session.borrow().mode;
+        if self.session.borrow().current.is_none() {
+            return Ok(());
+        }
+        if let Some(card) =
+            self.session
+                .borrow_mut()
+                .grade(&mut self.current_deck.borrow_mut(), grade, now)
+        {
+            card.clone().write_to_file(deck_path)?;
+        }
+        //Answers that leave the schedule alone are logged too, their interval just stays the same.
+        let record = {
+            let deck = self.current_deck.borrow();
+            let card = &deck.contents[item.card];
+            ReviewRecord {
+                card_id: Some(card.id),
+                card: card.title.clone(),
+                variant: item.variant,
+                deck: deck_title,
+                mode,
+                time: now,
+                grade,
+                response_ms: self.card_shown_at.elapsed().as_millis() as u64,
+                previous_interval,
+                next_interval: card.schedule(item.variant).interval,
+            }
+        };
+        ReviewLog::new(&self.options.config_directory).append(&record)?;
+        self.session
+            .borrow_mut()
+            .next(&mut self.current_deck.borrow_mut(), now);
+        self.show_current_card();
+        //Learning cards still to come keep the viewer open, otherwise the session is over.
+        if self.session.borrow().is_finished() && self.session.borrow().next_pending().is_none() {
+            self.state = Rc::new(ScreenState::SessionSummary);
+        }
+        Ok(())
+    }
+
     fn render_header(&self, f: &mut tui::Frame<CrosstermBackend<Stdout>>, area: &Rect) -> () {
         match *self.state.clone() {
+            ScreenState::TagBrowser => {
+                let titles = vec![Spans::from(vec![
+                    Span::raw("Tags: "),
+                    Span::raw(self.tags.len().to_string()),
+                ])];
+                let header = Paragraph::new(titles)
+                    .block(
+                        Block::default()
+                            .title(" Flash ")
+                            .borders(Borders::TOP | Borders::BOTTOM)
+                            .title_alignment(Alignment::Center),
+                    )
+                    .style(Style::default().fg(Color::White));
+                f.render_widget(header, *area);
+            }
             ScreenState::LocalMenu => {
                 let titles = vec![
                     Spans::from(Span::raw("Local")),
@@ -896,19 +1908,111 @@ impl Screen {
                 f.render_widget(header, *area);
             }
             ScreenState::DeckViewer => {
+                let mut progress = vec![
+                    Span::raw("Deck: "),
+                    Span::raw(self.current_deck.borrow().deck_title.clone()),
+                ];
+                {
+                    let deck = self.current_deck.borrow();
+                    if let (true, Some(source)) = (deck.is_merged(), deck.source(deck.cur_card)) {
+                        progress.push(Span::raw(" / "));
+                        progress.push(Span::styled(
+                            source.title.clone(),
+                            Style::default().fg(Color::Cyan),
+                        ));
+                    }
+                }
+                if self.session.borrow().mode == StudyMode::Cram {
+                    progress.push(Span::raw(" Cram: "));
+                    progress.push(Span::raw(self.session.borrow().remaining().to_string()));
+                    progress.push(Span::raw(" left"));
+                } else {
+                    let (new, learning, review) =
+                        self.session.borrow().counts(&self.current_deck.borrow());
+                    progress.extend(vec![
+                        Span::raw(" New / Learning / Review: "),
+                        Span::styled(new.to_string(), Style::default().fg(Color::Blue)),
+                        Span::raw(" / "),
+                        Span::styled(learning.to_string(), Style::default().fg(Color::Red)),
+                        Span::raw(" / "),
+                        Span::styled(review.to_string(), Style::default().fg(Color::Green)),
+                    ]);
+                }
+                let box_counts = self.current_deck.borrow().box_counts();
+                if !box_counts.is_empty() {
+                    progress.push(Span::raw(" Boxes:"));
+                    for (index, count) in box_counts.iter().enumerate() {
+                        progress.push(Span::raw(format!(" [{}] {}", index + 1, count)));
+                    }
+                }
+                let timer = self.timer.borrow();
+                if timer.settings.enabled {
+                    progress.push(Span::raw(format!(
+                        " Studied: {} Break in: {}",
+                        format_clock(timer.focused),
+                        format_clock(timer.until_break())
+                    )));
+                }
+                let titles = vec![Spans::from(progress)];
+                let header = Paragraph::new(titles)
+                    .block(
+                        Block::default()
+                            .title(" Flash ")
+                            .borders(Borders::TOP | Borders::BOTTOM)
+                            .title_alignment(Alignment::Center),
+                    )
+                    .style(Style::default().fg(Color::White));
+                f.render_widget(header, *area);
+            }
+            ScreenState::Exam => {
+                let exam = self.exam.borrow();
+                let remaining = exam.remaining(Utc::now());
+                let ratio = if exam.time_limit.num_milliseconds() > 0 {
+                    remaining.num_milliseconds() as f64 / exam.time_limit.num_milliseconds() as f64
+                } else {
+                    0.0
+                };
+                let label = format!(
+                    "{}:{:02} left  Question {}/{}  Answered {}",
+                    remaining.num_minutes(),
+                    remaining.num_seconds() % 60,
+                    exam.current + 1,
+                    exam.questions.len(),
+                    exam.answered()
+                );
+                let header = Gauge::default()
+                    .block(
+                        Block::default()
+                            .title(" Flash ")
+                            .borders(Borders::TOP | Borders::BOTTOM)
+                            .title_alignment(Alignment::Center),
+                    )
+                    .gauge_style(Style::default().fg(if ratio > 0.2 {
+                        Color::Green
+                    } else {
+                        Color::Red
+                    }))
+                    .ratio(ratio.clamp(0.0, 1.0))
+                    .label(label);
+                f.render_widget(header, *area);
+            }
+            ScreenState::Matching => {
+                let game = self.matching.borrow();
+                let elapsed = game.elapsed(Utc::now());
                 let titles = vec![Spans::from(vec![
                     Span::raw("Deck: "),
                     Span::raw(self.current_deck.borrow().deck_title.clone()),
-                    Span::raw(" Progress: "),
-                    Span::raw(
-                        (min(
-                            self.current_deck.borrow().cur_card.clone() + 1,
-                            self.current_deck.borrow().len(),
-                        ))
-                        .to_string(),
-                    ),
-                    Span::raw("/"),
-                    Span::raw((self.current_deck.borrow().len()).to_string()),
+                    Span::raw(format!(
+                        " Matched: {}/{} Errors: ",
+                        game.matched.iter().filter(|matched| **matched).count(),
+                        game.len()
+                    )),
+                    Span::styled(game.errors.to_string(), Style::default().fg(Color::Red)),
+                    Span::raw(format!(
+                        " Time: {}:{:02}",
+                        elapsed.num_minutes(),
+                        elapsed.num_seconds() % 60
+                    )),
                 ])];
                 let header = Paragraph::new(titles)
                     .block(
@@ -920,7 +2024,32 @@ impl Screen {
                     .style(Style::default().fg(Color::White));
                 f.render_widget(header, *area);
             }
-            ScreenState::DeckEditor => {
+            ScreenState::Break => {
+                let timer = self.timer.borrow();
+                let remaining = timer
+                    .break_until
+                    .map(|until| until - Utc::now())
+                    .unwrap_or_else(chrono::Duration::zero)
+                    .max(chrono::Duration::zero());
+                let ratio = if timer.settings.rest.num_milliseconds() > 0 {
+                    remaining.num_milliseconds() as f64
+                        / timer.settings.rest.num_milliseconds() as f64
+                } else {
+                    0.0
+                };
+                let header = Gauge::default()
+                    .block(
+                        Block::default()
+                            .title(" Flash ")
+                            .borders(Borders::TOP | Borders::BOTTOM)
+                            .title_alignment(Alignment::Center),
+                    )
+                    .gauge_style(Style::default().fg(Color::Cyan))
+                    .ratio(ratio.clamp(0.0, 1.0))
+                    .label(format!("Break: {} left", format_clock(remaining)));
+                f.render_widget(header, *area);
+            }
+            ScreenState::DeckEditor | ScreenState::SessionSummary | ScreenState::ExamReport => {
                 let titles = vec![Spans::from(vec![
                     Span::raw("Deck: "),
                     Span::raw(self.current_deck.borrow().deck_title.clone()),
@@ -939,7 +2068,35 @@ impl Screen {
     }
 
     fn render_footer(&self, f: &mut tui::Frame<CrosstermBackend<Stdout>>, area: &Rect) -> () {
+        if let Some(message) = &*self.error_message {
+            let footer = Paragraph::new(vec![Spans::from(Span::styled(
+                message.clone(),
+                Style::default().fg(Color::Red),
+            ))])
+            .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+            .alignment(Alignment::Left);
+            f.render_widget(footer, *area);
+            return;
+        }
         match *self.state.clone() {
+            ScreenState::TagBrowser => {
+                let text_vec = match *self.edit_mode {
+                    EditMode::StudyMenu(_) => {
+                        vec![Span::raw("Navigate (↑/↓) Select (Enter) Go Back (esc)")]
+                    }
+                    EditMode::ResumePrompt(_, _) => {
+                        vec![Span::raw("Resume (y/Enter) Start over (n) Go back (esc)")]
+                    }
+                    _ => vec![Span::raw(
+                        "Navigate (↑/↓) (s)tudy (e)dit Return to Menu (Esc) (q)uit",
+                    )],
+                };
+                let text = vec![Spans::from(text_vec)];
+                let footer = Paragraph::new(text)
+                    .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+                    .alignment(Alignment::Left);
+                f.render_widget(footer, *area);
+            }
             ScreenState::LocalMenu => match *self.edit_mode {
                 EditMode::None => {
                     let mut text_vec = vec![
@@ -950,14 +2107,20 @@ impl Screen {
                             .clone(),
                         ),
                         Span::raw("' Navigate (↑/↓) Select (Enter) "),
+                        Span::raw("(s)tudy options "),
+                        Span::raw("Mark (Space) "),
                         Span::raw("(e)dit "),
                         Span::raw("(d)elete "),
+                        Span::raw("(f)ilter "),
+                        Span::raw("(t)ags "),
                         Span::raw("(q)uit"),
                     ];
                     if self.local_menu_state.borrow().selected().unwrap()
                         == (self.local_decks_names.len() - 1)
                     {
-                        let keep = [true, true, true, false, false, true];
+                        let keep = [
+                            true, true, true, false, false, false, false, true, true, true,
+                        ];
                         let mut iter = keep.iter();
                         text_vec.retain(|_| *iter.next().unwrap());
                     }
@@ -967,7 +2130,7 @@ impl Screen {
                         .alignment(Alignment::Left);
                     f.render_widget(footer, *area);
                 }
-                EditMode::EditMenu(_) => {
+                EditMode::EditMenu(_) | EditMode::StudyMenu(_) => {
                     let text_vec = vec![Span::raw("Navigate (↑/↓) Select (Enter) Go Back (esc)")];
                     let text = vec![Spans::from(text_vec)];
                     let footer = Paragraph::new(text)
@@ -975,7 +2138,15 @@ impl Screen {
                         .alignment(Alignment::Left);
                     f.render_widget(footer, *area);
                 }
-                EditMode::AddItem => {
+                EditMode::ResumePrompt(_, _) => {
+                    let text_vec = vec![Span::raw("Resume (y/Enter) Start over (n) Go back (esc)")];
+                    let text = vec![Spans::from(text_vec)];
+                    let footer = Paragraph::new(text)
+                        .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+                        .alignment(Alignment::Left);
+                    f.render_widget(footer, *area);
+                }
+                EditMode::AddItem | EditMode::AddFilter | EditMode::EditFilter => {
                     let text_vec = vec![Span::raw("Save (Enter) Go back (esc) Undo (Backspace)")];
                     let text = vec![Spans::from(text_vec)];
                     let footer = Paragraph::new(text)
@@ -993,15 +2164,108 @@ impl Screen {
                 }
                 _ => (),
             },
+            ScreenState::DeckViewer if matches!(*self.edit_mode, EditMode::ChooseAnswer(_)) => {
+                let text = vec![Spans::from(vec![Span::raw(
+                    "Navigate (↑/↓) Choose (Enter/1-4) Return to Menu (Esc) (q)uit",
+                )])];
+                let footer = Paragraph::new(text)
+                    .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+                    .alignment(Alignment::Left);
+                f.render_widget(footer, *area);
+            }
+            ScreenState::DeckViewer if self.question.is_some() => {
+                let text = vec![Spans::from(vec![Span::raw(
+                    "Previous/Next Section (←/→) Continue (Enter) Return to Menu (Esc) (q)uit",
+                )])];
+                let footer = Paragraph::new(text)
+                    .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+                    .alignment(Alignment::Left);
+                f.render_widget(footer, *area);
+            }
+            ScreenState::DeckViewer if matches!(*self.edit_mode, EditMode::TypeAnswer) => {
+                let text = vec![Spans::from(vec![Span::raw(
+                    "Check Answer (Enter) Undo (Backspace) Return to Menu (Esc) (ctrl-q)uit",
+                )])];
+                let footer = Paragraph::new(text)
+                    .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+                    .alignment(Alignment::Left);
+                f.render_widget(footer, *area);
+            }
             ScreenState::DeckViewer => {
+                let deck = self.current_deck.borrow();
+                let mut text_vec = vec![
+                    Span::raw("Previous/Next Section (←/→) "),
+                    Span::raw("Scroll Code (shift-←/→) "),
+                    Span::raw("Again/Hard/Good/Easy (1-4) "),
+                    Span::raw("Return to Menu (Esc) (q)uit"),
+                ];
+                //Scrolling is only offered when there is code too wide for the panel.
+                let width = Screen::build_layout(f)[2].width;
+                let scrolls = deck
+                    .contents
+                    .get(deck.cur_card)
+                    .is_some_and(|card| card.code_overflow(deck.cur_variant, width) > 0);
+                //Grading is only offered once the whole card has been revealed.
+                let grades = !self.session.borrow().is_finished() && deck.is_revealed();
+                if !scrolls || !grades {
+                    let keep = [true, scrolls, grades, true];
+                    let mut iter = keep.iter();
+                    text_vec.retain(|_| *iter.next().unwrap());
+                }
+                let text = vec![Spans::from(text_vec)];
+                let footer = Paragraph::new(text)
+                    .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+                    .alignment(Alignment::Left);
+                f.render_widget(footer, *area);
+            }
+            ScreenState::Exam => {
                 let text = vec![Spans::from(vec![Span::raw(
-                    "Next Section/Next Card (←/→) Return to Menu (Esc) (q)uit",
+                    "Navigate (↑/↓) Answer (Enter/1-4) Previous/Next Question (←/→) Hand In (Esc)",
                 )])];
                 let footer = Paragraph::new(text)
                     .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
                     .alignment(Alignment::Left);
                 f.render_widget(footer, *area);
             }
+            ScreenState::Matching => {
+                let text = vec![Spans::from(vec![Span::raw(
+                    "Move (←/→/↑/↓) Pick (Enter/click) New Game (r) Return to Menu (Esc) (q)uit",
+                )])];
+                let footer = Paragraph::new(text)
+                    .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+                    .alignment(Alignment::Left);
+                f.render_widget(footer, *area);
+            }
+            ScreenState::Break => {
+                let text = vec![Spans::from(vec![Span::raw(
+                    "Back to Studying (Enter) Return to Menu (Esc) (q)uit",
+                )])];
+                let footer = Paragraph::new(text)
+                    .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+                    .alignment(Alignment::Left);
+                f.render_widget(footer, *area);
+            }
+            ScreenState::ExamReport => {
+                let text = vec![Spans::from(vec![Span::raw("Return to Menu (Esc) (q)uit")])];
+                let footer = Paragraph::new(text)
+                    .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+                    .alignment(Alignment::Left);
+                f.render_widget(footer, *area);
+            }
+            ScreenState::SessionSummary => {
+                let mut text_vec = vec![
+                    Span::raw("Re-study Missed Cards (r) "),
+                    Span::raw("Return to Menu (Esc) (q)uit"),
+                ];
+                if self.session.borrow().missed.is_empty() {
+                    text_vec.remove(0);
+                }
+                let text = vec![Spans::from(text_vec)];
+                let footer = Paragraph::new(text)
+                    .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+                    .alignment(Alignment::Left);
+                f.render_widget(footer, *area);
+            }
             ScreenState::DeckEditor => match *self.edit_mode {
                 EditMode::None => {
                     let mut temp_vec = self.current_deck.borrow().get_card_names();
@@ -1014,12 +2278,14 @@ impl Screen {
                         Span::raw("' Navigate (↑/↓) Select (Enter) "),
                         Span::raw("(e)dit "),
                         Span::raw("(d)elete "),
+                        Span::raw("(s)uspend "),
+                        Span::raw("Move (shift-↑/↓) "),
                         Span::raw("(q)uit"),
                     ];
                     if self.edit_menu_state.borrow().selected().unwrap()
                         == (self.current_deck.borrow().len())
                     {
-                        let keep = [true, true, true, false, false, true];
+                        let keep = [true, true, true, false, false, false, false, true];
                         let mut iter = keep.iter();
                         text_vec.retain(|_| *iter.next().unwrap());
                     }
@@ -1041,7 +2307,7 @@ impl Screen {
                         .alignment(Alignment::Left);
                     f.render_widget(footer, *area);
                 }
-                EditMode::EditTitle => {
+                EditMode::EditTitle | EditMode::EditTags => {
                     let text_vec = vec![Span::raw("Save (Enter) Go back (esc) Undo (Backspace)")];
                     let text = vec![Spans::from(text_vec)];
                     let footer = Paragraph::new(text)
@@ -1068,11 +2334,41 @@ impl Screen {
         area: &Rect,
     ) -> () {
         match *self.state.clone() {
+            ScreenState::TagBrowser => {
+                let list_items: Vec<ListItem> = self
+                    .tags
+                    .iter()
+                    .map(|(tag, titles)| {
+                        ListItem::new(Spans::from(vec![
+                            Span::raw(format!("#{}", tag)),
+                            Span::styled(
+                                format!(" ({} cards)", titles.len()),
+                                Style::default().fg(Color::Cyan),
+                            ),
+                        ]))
+                    })
+                    .collect();
+                let middle_panel = List::new(list_items)
+                    .block(Block::default().borders(Borders::ALL))
+                    .style(Style::default().fg(Color::White))
+                    .highlight_style(Style::default().bg(Color::White).fg(Color::Black));
+                f.render_stateful_widget(middle_panel, *area, &mut self.tag_state.borrow_mut());
+            }
             ScreenState::LocalMenu => {
                 let list_items: Vec<ListItem> = self
                     .local_decks_names
                     .iter()
-                    .map(|x| ListItem::new(x.to_owned()))
+                    .map(|x| {
+                        let mut name = x.to_owned();
+                        if Filter::is_filter(&self.options.local_directory.join(x)) {
+                            name.push_str(" (filtered)");
+                        }
+                        if self.marked_decks.borrow().contains(x) {
+                            ListItem::new(format!("✓ {}", name))
+                        } else {
+                            ListItem::new(name)
+                        }
+                    })
                     .collect();
                 let middle_panel = List::new(list_items)
                     .block(Block::default().borders(Borders::ALL))
@@ -1086,15 +2382,236 @@ impl Screen {
                 );
             }
             ScreenState::DeckViewer => {
-                f.render_widget(self.current_deck.borrow().as_widget(), *area)
+                if self.session.borrow().is_finished() {
+                    let message = match self.session.borrow().next_pending() {
+                        _ if self.session.borrow().mode == StudyMode::Cram => {
+                            String::from("Cram session finished, good luck!")
+                        }
+                        Some(due) => format!(
+                            "The next learning card comes back at {}.",
+                            due.with_timezone(&Local).format("%H:%M")
+                        ),
+                        None => String::from("No cards are due, come back later!"),
+                    };
+                    let text = vec![Spans::from(message)];
+                    let middle_panel = Paragraph::new(text)
+                        .block(Block::default().borders(Borders::ALL))
+                        .alignment(Alignment::Center);
+                    f.render_widget(middle_panel, *area);
+                } else {
+                    f.render_widget(self.current_deck.borrow().as_widget(area.width), *area)
+                }
+            }
+            ScreenState::Exam => {
+                f.render_widget(self.current_deck.borrow().as_widget(area.width), *area)
+            }
+            ScreenState::Break => {
+                let timer = self.timer.borrow();
+                let message = match timer.break_until {
+                    Some(until) if until > Utc::now() => "Time for a break, step away for a bit.",
+                    _ => "Break's over, press Enter to get back to studying.",
+                };
+                let today = timer.studied_today;
+                let text = vec![
+                    Spans::from(message),
+                    Spans::from(""),
+                    Spans::from(format!(
+                        "Studied this session: {}",
+                        format_clock(timer.focused)
+                    )),
+                    Spans::from(format!(
+                        "Studied today: {}h {:02}m",
+                        today / 3600,
+                        today % 3600 / 60
+                    )),
+                ];
+                let middle_panel = Paragraph::new(text)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(" Break ")
+                            .title_alignment(Alignment::Center),
+                    )
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true });
+                f.render_widget(middle_panel, *area);
+            }
+            ScreenState::Matching => {
+                let game = self.matching.borrow();
+                let cursor = *self.matching_cursor.borrow();
+                let columns = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                    .split(*area);
+                let entries = [
+                    game.fronts.iter().collect::<Vec<&String>>(),
+                    game.backs.iter().map(|(_, back)| back).collect(),
+                ];
+                for (index, column) in [Column::Fronts, Column::Backs].into_iter().enumerate() {
+                    let list_items: Vec<ListItem> = entries[index]
+                        .iter()
+                        .enumerate()
+                        .map(|(row, entry)| {
+                            let wrong = match (game.wrong, column) {
+                                (Some((front, _)), Column::Fronts) => front == row,
+                                (Some((_, back)), Column::Backs) => back == row,
+                                _ => false,
+                            };
+                            let style = if game.is_matched(column, row) {
+                                Style::default().fg(Color::DarkGray)
+                            } else if wrong {
+                                Style::default().fg(Color::Red)
+                            } else if game.selected[index] == Some(row) {
+                                Style::default()
+                                    .fg(Color::Yellow)
+                                    .add_modifier(Modifier::BOLD)
+                            } else {
+                                Style::default().fg(Color::White)
+                            };
+                            ListItem::new(entry.to_string()).style(style)
+                        })
+                        .collect();
+                    let mut state = ListState::default();
+                    if cursor.0 == column {
+                        state.select(Some(cursor.1));
+                    }
+                    let heights: Vec<usize> = list_items.iter().map(|item| item.height()).collect();
+                    self.matching_rows.borrow_mut()[index] = util::visible_rows(
+                        &heights,
+                        state.selected(),
+                        columns[index].height.saturating_sub(2) as usize,
+                    );
+                    let list = List::new(list_items)
+                        .block(Block::default().borders(Borders::ALL).title(match column {
+                            Column::Fronts => " Fronts ",
+                            Column::Backs => " Backs ",
+                        }))
+                        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                    f.render_stateful_widget(list, columns[index], &mut state);
+                }
+                *self.matching_areas.borrow_mut() = [columns[0], columns[1]];
+            }
+            ScreenState::ExamReport => {
+                if let Some((report, file_path)) = &*self.exam_report {
+                    let used = report.finished - report.started;
+                    let mut text = vec![
+                        Spans::from(format!(
+                            "Score: {}/{} ({:.0}%)",
+                            report.score,
+                            report.total,
+                            report.percentage()
+                        )),
+                        Spans::from(format!(
+                            "Time used: {}m {:02}s of {}m",
+                            used.num_minutes(),
+                            used.num_seconds() % 60,
+                            report.time_limit_seconds / 60
+                        )),
+                        Spans::from(match file_path {
+                            Ok(file_path) => format!("Report saved to {}", file_path.display()),
+                            Err(err) => format!("Couldn't save the report: {}", err),
+                        }),
+                        Spans::from(""),
+                    ];
+                    for answer in report.answers.iter().filter(|answer| !answer.correct) {
+                        text.push(Spans::from(vec![
+                            Span::raw(format!("{}: ", answer.card)),
+                            Span::styled(
+                                answer
+                                    .chosen
+                                    .clone()
+                                    .unwrap_or_else(|| String::from("(no answer)")),
+                                Style::default().fg(Color::Red),
+                            ),
+                            Span::raw(" → "),
+                            Span::styled(answer.answer.clone(), Style::default().fg(Color::Green)),
+                        ]));
+                    }
+                    let middle_panel = Paragraph::new(text)
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .title(" Exam Report ")
+                                .title_alignment(Alignment::Center),
+                        )
+                        .wrap(Wrap { trim: false });
+                    f.render_widget(middle_panel, *area);
+                }
+            }
+            ScreenState::SessionSummary => {
+                let session = self.session.borrow();
+                let deck = self.current_deck.borrow();
+                let elapsed = session.elapsed(Utc::now());
+                let mut text = vec![
+                    Spans::from(format!("Cards answered: {}", session.answered())),
+                    Spans::from(format!(
+                        "Accuracy: {:.0}%",
+                        session.accuracy().unwrap_or_default() * 100.0
+                    )),
+                    Spans::from(format!(
+                        "Time spent: {}m {:02}s",
+                        elapsed.num_minutes(),
+                        elapsed.num_seconds() % 60
+                    )),
+                    Spans::from(""),
+                ];
+                if session.missed.is_empty() {
+                    text.push(Spans::from("No cards missed, well done!"));
+                } else {
+                    text.push(Spans::from(format!(
+                        "Missed cards ({}):",
+                        session.missed.len()
+                    )));
+                    for item in session.missed.iter() {
+                        text.push(Spans::from(Span::styled(
+                            deck.item_title(*item),
+                            Style::default().fg(Color::Red),
+                        )));
+                    }
+                }
+                let middle_panel = Paragraph::new(text).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(" Session Summary ")
+                        .title_alignment(Alignment::Center),
+                );
+                f.render_widget(middle_panel, *area);
             }
             ScreenState::DeckEditor => {
-                let mut name_vec = self.current_deck.borrow().get_card_names();
-                name_vec.push(String::from("Add new card..."));
-                let list_items: Vec<ListItem> = name_vec
+                let deck = self.current_deck.borrow();
+                let mut list_items: Vec<ListItem> = deck
+                    .contents
                     .iter()
-                    .map(|name| ListItem::new(name.to_owned()))
+                    .map(|card| {
+                        let mut line = Vec::new();
+                        if card.leech {
+                            line.push(Span::styled("⚑ ", Style::default().fg(Color::Red)));
+                        }
+                        line.push(Span::raw(card.title.to_owned()));
+                        //Cloze and two way cards show how many study items they turn into.
+                        let items = card.variants(deck.settings.bidirectional).len();
+                        if items > 1 {
+                            line.push(Span::styled(
+                                format!(" ({} items)", items),
+                                Style::default().fg(Color::Cyan),
+                            ));
+                        }
+                        if card.suspended {
+                            line.push(Span::styled(
+                                " (suspended)",
+                                Style::default().fg(Color::DarkGray),
+                            ));
+                        }
+                        for tag in card.tags.iter() {
+                            line.push(Span::styled(
+                                format!(" #{}", tag),
+                                Style::default().fg(Color::Yellow),
+                            ));
+                        }
+                        ListItem::new(Spans::from(line))
+                    })
                     .collect();
+                list_items.push(ListItem::new("Add new card..."));
                 let middle_panel = List::new(list_items)
                     .block(Block::default().borders(Borders::ALL))
                     .style(Style::default().fg(Color::White))
@@ -1115,6 +2632,36 @@ impl Screen {
         area: &Rect,
     ) -> () {
         match *self.state.clone() {
+            ScreenState::TagBrowser => match &*self.edit_mode {
+                EditMode::StudyMenu(menu_state) => {
+                    let list_items: Vec<ListItem> = StudyOption::ALL
+                        .iter()
+                        .map(|option| ListItem::new(option.label()))
+                        .collect();
+                    let right_panel = List::new(list_items)
+                        .block(Block::default().borders(Borders::ALL).title(" Study Menu "))
+                        .style(Style::default().fg(Color::White))
+                        .highlight_style(Style::default().bg(Color::White).fg(Color::Black));
+                    f.render_stateful_widget(right_panel, *area, &mut menu_state.borrow_mut())
+                }
+                EditMode::ResumePrompt(_, saved) => Screen::render_resume_prompt(f, area, *saved),
+                _ => {
+                    let text: Vec<Spans> = match self.tag_state.borrow().selected() {
+                        Some(selected) if selected < self.tags.len() => self.tags[selected]
+                            .1
+                            .iter()
+                            .map(|title| Spans::from(title.clone()))
+                            .collect(),
+                        _ => vec![Spans::from(
+                            "No tags yet, add some to cards from the deck editor.",
+                        )],
+                    };
+                    let right_panel = Paragraph::new(text)
+                        .block(Block::default().borders(Borders::ALL).title(" Cards "))
+                        .wrap(Wrap { trim: true });
+                    f.render_widget(right_panel, *area);
+                }
+            },
             ScreenState::LocalMenu => match &*self.edit_mode {
                 EditMode::EditMenu(menu_state) => {
                     let list_items = vec![
@@ -1127,6 +2674,17 @@ impl Screen {
                         .highlight_style(Style::default().bg(Color::White).fg(Color::Black));
                     f.render_stateful_widget(right_panel, *area, &mut menu_state.borrow_mut())
                 }
+                EditMode::StudyMenu(menu_state) => {
+                    let list_items: Vec<ListItem> = StudyOption::ALL
+                        .iter()
+                        .map(|option| ListItem::new(option.label()))
+                        .collect();
+                    let right_panel = List::new(list_items)
+                        .block(Block::default().borders(Borders::ALL).title(" Study Menu "))
+                        .style(Style::default().fg(Color::White))
+                        .highlight_style(Style::default().bg(Color::White).fg(Color::Black));
+                    f.render_stateful_widget(right_panel, *area, &mut menu_state.borrow_mut())
+                }
                 EditMode::AddItem => {
                     let text = vec![Spans::from((*self.right_panel_text_field).clone())];
 
@@ -1153,6 +2711,44 @@ impl Screen {
                         f.render_widget(right_panel_error, right_panel_layout[1]);
                     }
                 }
+                EditMode::ResumePrompt(_, saved) => Screen::render_resume_prompt(f, area, *saved),
+                EditMode::AddFilter | EditMode::EditFilter => {
+                    let text = vec![Spans::from((*self.right_panel_text_field).clone())];
+                    let title = if matches!(*self.edit_mode, EditMode::AddFilter) {
+                        " New Filtered Deck Name "
+                    } else {
+                        " Search Query "
+                    };
+                    let right_panel = Paragraph::new(text)
+                        .block(Block::default().borders(Borders::ALL).title(title));
+                    let help = vec![
+                        Spans::from("Words match card text, all terms have to match."),
+                        Spans::from("deck:name  tag:name  is:due  is:new  is:learning"),
+                        Spans::from("is:suspended  is:leech  lapses>n  lapses<n"),
+                        Spans::from("reviewed:days  missed:days  -term to exclude"),
+                    ];
+                    let right_panel_help = Paragraph::new(help)
+                        .block(Block::default().borders(Borders::ALL).title(" Help "))
+                        .wrap(Wrap { trim: true });
+                    let right_panel_layout = Layout::default()
+                        .constraints(
+                            [
+                                Constraint::Percentage(20),
+                                Constraint::Percentage(20),
+                                Constraint::Percentage(60),
+                            ]
+                            .as_ref(),
+                        )
+                        .split(*area);
+                    f.render_widget(right_panel, right_panel_layout[0]);
+                    if self.edit_failed {
+                        let error_text = vec![Spans::from("Deck already exists.")];
+                        let right_panel_error = Paragraph::new(error_text)
+                            .block(Block::default().borders(Borders::ALL).title(" Error "));
+                        f.render_widget(right_panel_error, right_panel_layout[1]);
+                    }
+                    f.render_widget(right_panel_help, right_panel_layout[2]);
+                }
                 EditMode::EditTitle => {
                     let text = vec![Spans::from((*self.right_panel_text_field).clone())];
 
@@ -1186,6 +2782,7 @@ impl Screen {
                     let list_items = vec![
                         ListItem::new("Edit Card Name"),
                         ListItem::new("Edit/Add Sections"),
+                        ListItem::new("Edit Tags"),
                     ];
                     let right_panel = List::new(list_items)
                         .block(Block::default().borders(Borders::ALL).title(" Edit Menu "))
@@ -1212,21 +2809,20 @@ impl Screen {
                         )
                         .split(*area);
                     f.render_widget(right_panel, right_panel_layout[0]);
-                    if self.edit_failed {
-                        let error_text = vec![Spans::from("Card already exists.")];
-                        let right_panel_error = Paragraph::new(error_text)
-                            .block(Block::default().borders(Borders::ALL).title(" Error "));
-                        f.render_widget(right_panel_error, right_panel_layout[1]);
-                    }
                 }
                 EditMode::EditContent => {
                     let text = vec![Spans::from((*self.right_panel_text_field).clone())];
+                    let deck = self.current_deck.borrow();
+                    let title = deck
+                        .contents
+                        .get(deck.cur_card)
+                        .and_then(|card| card.section_name(card.current_section))
+                        .map(|field| format!(" {} ", field))
+                        .unwrap_or_else(|| String::from(" Change Card Section "));
+                    drop(deck);
 
-                    let right_panel = Paragraph::new(text).block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .title(" Change Card Section "),
-                    );
+                    let right_panel = Paragraph::new(text)
+                        .block(Block::default().borders(Borders::ALL).title(title));
                     let right_panel_layout = Layout::default()
                         .constraints(
                             [Constraint::Percentage(80), Constraint::Percentage(20)].as_ref(),
@@ -1240,6 +2836,29 @@ impl Screen {
                         f.render_widget(right_panel_error, right_panel_layout[1]);
                     }
                 }
+                EditMode::EditTags => {
+                    let text = vec![Spans::from((*self.right_panel_text_field).clone())];
+                    let right_panel = Paragraph::new(text)
+                        .block(Block::default().borders(Borders::ALL).title(" Tags "));
+                    let help = vec![Spans::from(
+                        "Separate tags with spaces, browse them with t from the main menu.",
+                    )];
+                    let right_panel_help = Paragraph::new(help)
+                        .block(Block::default().borders(Borders::ALL).title(" Help "))
+                        .wrap(Wrap { trim: true });
+                    let right_panel_layout = Layout::default()
+                        .constraints(
+                            [
+                                Constraint::Percentage(20),
+                                Constraint::Percentage(20),
+                                Constraint::Percentage(60),
+                            ]
+                            .as_ref(),
+                        )
+                        .split(*area);
+                    f.render_widget(right_panel, right_panel_layout[0]);
+                    f.render_widget(right_panel_help, right_panel_layout[1]);
+                }
                 EditMode::EditTitle => {
                     let text = vec![Spans::from((*self.right_panel_text_field).clone())];
 
@@ -1259,15 +2878,146 @@ impl Screen {
                         )
                         .split(*area);
                     f.render_widget(right_panel, right_panel_layout[0]);
-                    if self.edit_failed {
-                        let error_text = vec![Spans::from("Card already exists.")];
-                        let right_panel_error = Paragraph::new(error_text)
-                            .block(Block::default().borders(Borders::ALL).title(" Error "));
-                        f.render_widget(right_panel_error, right_panel_layout[1]);
-                    }
                 }
                 _ => (),
             },
+            ScreenState::Matching => {
+                let game = self.matching.borrow();
+                let text = match game.finished {
+                    Some(_) => {
+                        let elapsed = game.elapsed(Utc::now());
+                        vec![
+                            Spans::from(Span::styled(
+                                "All pairs matched!",
+                                Style::default().fg(Color::Green),
+                            )),
+                            Spans::from(format!(
+                                "{} pairs in {}m {:02}s with {} errors.",
+                                game.len(),
+                                elapsed.num_minutes(),
+                                elapsed.num_seconds() % 60,
+                                game.errors
+                            )),
+                            Spans::from(""),
+                            Spans::from("Press r to play again."),
+                        ]
+                    }
+                    None => vec![Spans::from(
+                        "Pick a front and the back that goes with it, by keyboard or mouse.",
+                    )],
+                };
+                let right_panel = Paragraph::new(text)
+                    .block(Block::default().borders(Borders::ALL).title(" Matching "))
+                    .wrap(Wrap { trim: false });
+                f.render_widget(right_panel, *area);
+            }
+            ScreenState::Exam => {
+                if let Some(question) = self.exam.borrow().current_question() {
+                    let list_items: Vec<ListItem> = question
+                        .choices
+                        .iter()
+                        .enumerate()
+                        .map(|(index, choice)| {
+                            let marker = if question.chosen == Some(index) {
+                                "● "
+                            } else {
+                                "  "
+                            };
+                            ListItem::new(format!("{}{}. {}", marker, index + 1, choice))
+                        })
+                        .collect();
+                    let right_panel = List::new(list_items)
+                        .block(Block::default().borders(Borders::ALL).title(" Choices "))
+                        .style(Style::default().fg(Color::White))
+                        .highlight_style(Style::default().bg(Color::White).fg(Color::Black));
+                    f.render_stateful_widget(
+                        right_panel,
+                        *area,
+                        &mut self.exam_choice_state.borrow_mut(),
+                    )
+                }
+            }
+            ScreenState::DeckViewer => match &*self.edit_mode {
+                EditMode::ChooseAnswer(choice_state) => {
+                    if let Some(question) = &*self.question {
+                        let list_items: Vec<ListItem> = question
+                            .choices
+                            .iter()
+                            .enumerate()
+                            .map(|(index, choice)| {
+                                ListItem::new(format!("{}. {}", index + 1, choice))
+                            })
+                            .collect();
+                        let right_panel = List::new(list_items)
+                            .block(Block::default().borders(Borders::ALL).title(" Choices "))
+                            .style(Style::default().fg(Color::White))
+                            .highlight_style(Style::default().bg(Color::White).fg(Color::Black));
+                        f.render_stateful_widget(right_panel, *area, &mut choice_state.borrow_mut())
+                    }
+                }
+                _ if self.question.is_some() => {
+                    if let Some(question) = &*self.question {
+                        //The right choice turns green, a wrong pick turns red.
+                        let mut list_items: Vec<ListItem> = question
+                            .choices
+                            .iter()
+                            .enumerate()
+                            .map(|(index, choice)| {
+                                let style = if index == question.correct {
+                                    Style::default().fg(Color::Green)
+                                } else if Some(index) == question.chosen {
+                                    Style::default().fg(Color::Red)
+                                } else {
+                                    Style::default().fg(Color::DarkGray)
+                                };
+                                ListItem::new(format!("{}. {}", index + 1, choice)).style(style)
+                            })
+                            .collect();
+                        list_items.push(ListItem::new(""));
+                        list_items.push(if question.is_correct() {
+                            ListItem::new("Correct!").style(Style::default().fg(Color::Green))
+                        } else {
+                            ListItem::new("Not quite.").style(Style::default().fg(Color::Red))
+                        });
+                        let right_panel = List::new(list_items)
+                            .block(Block::default().borders(Borders::ALL).title(" Choices "));
+                        f.render_widget(right_panel, *area);
+                    }
+                }
+                EditMode::TypeAnswer => {
+                    let text = vec![Spans::from((*self.right_panel_text_field).clone())];
+                    let right_panel = Paragraph::new(text)
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .title(" Your Answer "),
+                        )
+                        .wrap(Wrap { trim: false });
+                    f.render_widget(right_panel, *area);
+                }
+                _ => {
+                    if let Some(diffs) = &*self.answer_diff {
+                        let verdict = if answer::is_correct(diffs) {
+                            Span::styled("Correct!", Style::default().fg(Color::Green))
+                        } else {
+                            Span::styled("Not quite.", Style::default().fg(Color::Red))
+                        };
+                        let text = vec![
+                            answer::as_spans(diffs),
+                            Spans::from(""),
+                            Spans::from(verdict),
+                        ];
+                        let right_panel = Paragraph::new(text)
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title(" Your Answer "),
+                            )
+                            .wrap(Wrap { trim: false });
+                        f.render_widget(right_panel, *area);
+                    }
+                }
+            },
             _ => (),
         }
     }
@@ -1309,6 +3059,29 @@ impl Screen {
         ];
     }
 
+    fn render_resume_prompt(
+        f: &mut tui::Frame<CrosstermBackend<Stdout>>,
+        area: &Rect,
+        saved: DateTime<Utc>,
+    ) {
+        let text = vec![
+            Spans::from(format!(
+                "A session on this deck was left on {}.",
+                saved.with_timezone(&Local).format("%Y-%m-%d at %H:%M")
+            )),
+            Spans::from(""),
+            Spans::from("Pick it up where you left off?"),
+        ];
+        let right_panel = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Resume Session "),
+            )
+            .wrap(Wrap { trim: true });
+        f.render_widget(right_panel, *area);
+    }
+
     fn get_current_local_decks(options: &ScreenOptions) -> Vec<String> {
         let cur_dir = options.local_directory.clone();
         let mut list_items_str =
@@ -1318,3 +3091,12 @@ impl Screen {
         list_items_str
     }
 }
+
+//Durations on the study timer are shown as minutes and seconds.
+fn format_clock(duration: chrono::Duration) -> String {
+    format!(
+        "{}:{:02}",
+        duration.num_minutes(),
+        duration.num_seconds().rem_euclid(60)
+    )
+}
@@ -0,0 +1,467 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    card::{Card, Variant},
+    deck::{Deck, Item},
+    scheduler::{CardState, Grade, Schedule},
+    util,
+};
+
+pub const SESSIONS_DIRECTORY: &str = "sessions";
+
+//How far ahead of time a learning card may be shown when there is nothing else left to study.
+const LEARN_AHEAD_MINUTES: i64 = 20;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum StudyMode {
+    #[default]
+    Review,
+    //Cramming goes through cards without touching their scheduling data.
+    Cram,
+}
+
+//How a card is answered before it gets graded.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AnswerStyle {
+    #[default]
+    Flip,
+    //The answer is typed in and checked against the card.
+    Typed,
+    //The answer is picked from a list of choices and graded automatically.
+    MultipleChoice,
+}
+
+//The ways a deck can be studied, as listed in the study menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StudyOption {
+    Review,
+    CramRandom,
+    CramWeakest,
+    CramMissed,
+    TypeAnswer,
+    Quiz,
+    Exam,
+    Matching,
+}
+
+impl StudyOption {
+    pub const ALL: [StudyOption; 8] = [
+        StudyOption::Review,
+        StudyOption::TypeAnswer,
+        StudyOption::Quiz,
+        StudyOption::CramRandom,
+        StudyOption::CramWeakest,
+        StudyOption::CramMissed,
+        StudyOption::Exam,
+        StudyOption::Matching,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StudyOption::Review => "Review Due Cards",
+            StudyOption::CramRandom => "Cram All Cards (Random)",
+            StudyOption::CramWeakest => "Cram All Cards (Weakest First)",
+            StudyOption::CramMissed => "Cram Missed Cards",
+            StudyOption::TypeAnswer => "Review Due Cards (Type the Answer)",
+            StudyOption::Quiz => "Multiple Choice Quiz",
+            StudyOption::Exam => "Timed Exam",
+            StudyOption::Matching => "Matching Game",
+        }
+    }
+}
+
+//The cards of one study session, kept apart from the deck so cards can come back around mid session.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Session {
+    pub mode: StudyMode,
+    queue: Vec<Item>,
+    learning: Vec<(Item, DateTime<Utc>)>,
+    pub current: Option<Item>,
+    pub answer_style: AnswerStyle,
+    //What gets shown on the summary screen once the session is over.
+    started: Option<DateTime<Utc>>,
+    answered: usize,
+    correct: usize,
+    pub missed: Vec<Item>,
+}
+
+impl Session {
+    pub fn new(deck: &Deck, now: DateTime<Utc>) -> Self {
+        //Cards left mid step by an earlier session come back when their step is up.
+        let learning = deck
+            .items((0..deck.len()).collect())
+            .into_iter()
+            .filter(|item| {
+                !deck.contents[item.card].suspended && deck.schedule(*item).learning_step.is_some()
+            })
+            .filter_map(|item| deck.schedule(item).due.map(|due| (item, due)))
+            .filter(|(_, due)| *due > now)
+            .collect();
+        Session {
+            mode: StudyMode::Review,
+            queue: deck.available_items(now),
+            learning,
+            ..Session::default()
+        }
+    }
+
+    pub fn start(option: StudyOption, deck: &Deck, now: DateTime<Utc>) -> Self {
+        match option {
+            StudyOption::Review => Session::new(deck, now),
+            StudyOption::CramRandom => Session::cram(deck, false, false),
+            StudyOption::CramWeakest => Session::cram(deck, false, true),
+            StudyOption::CramMissed => Session::cram(deck, true, true),
+            StudyOption::TypeAnswer => Session {
+                answer_style: AnswerStyle::Typed,
+                ..Session::new(deck, now)
+            },
+            //Picking from choices is too easy to count as recall, so quizzes leave scheduling alone.
+            StudyOption::Quiz => Session {
+                answer_style: AnswerStyle::MultipleChoice,
+                ..Session::restudy(
+                    deck.items(deck.study_order())
+                        .into_iter()
+                        .filter(|item| !deck.contents[item.card].suspended)
+                        .collect(),
+                )
+            },
+            //Exams and matching games keep their own questions, see Exam and MatchGame.
+            StudyOption::Exam | StudyOption::Matching => Session::default(),
+        }
+    }
+
+    //Every card, or only the ones that have been missed, either shuffled or weakest memory first.
+    pub fn cram(deck: &Deck, only_missed: bool, weakest_first: bool) -> Self {
+        let order = if weakest_first {
+            deck.study_order()
+        } else {
+            deck.shuffled_order()
+        };
+        let mut queue: Vec<Item> = deck
+            .items(order)
+            .into_iter()
+            .filter(|item| {
+                let schedule = deck.schedule(*item);
+                !deck.contents[item.card].suspended
+                    && (!only_missed
+                        || deck.contents[item.card].leech
+                        || schedule.lapses > 0
+                        || matches!(schedule.reviews.last(), Some(review) if review.grade == Grade::Again))
+            })
+            .collect();
+        if weakest_first {
+            queue.sort_by(|a, b| {
+                let a = deck.schedule(*a);
+                let b = deck.schedule(*b);
+                a.interval
+                    .cmp(&b.interval)
+                    .then(b.lapses.cmp(&a.lapses))
+                    .then(a.ease_factor.total_cmp(&b.ease_factor))
+            });
+        }
+        Session::restudy(queue)
+    }
+
+    //Goes over exactly the given cards as a cram session, used to re-study the misses of a session.
+    pub fn restudy(queue: Vec<Item>) -> Self {
+        Session {
+            mode: StudyMode::Cram,
+            queue,
+            ..Session::default()
+        }
+    }
+
+    pub fn answered(&self) -> usize {
+        self.answered
+    }
+
+    //The share of answers that were not graded Again.
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.answered > 0).then(|| self.correct as f64 / self.answered as f64)
+    }
+
+    pub fn elapsed(&self, now: DateTime<Utc>) -> Duration {
+        self.started
+            .map(|started| now - started)
+            .unwrap_or_else(Duration::zero)
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.queue.len() + self.learning.len() + self.current.iter().count()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current.is_none()
+    }
+
+    //When the next learning card comes back, if any are waiting.
+    pub fn next_pending(&self) -> Option<DateTime<Utc>> {
+        self.learning.iter().map(|(_, due)| *due).min()
+    }
+
+    //Learning cards whose step is up come first, then the scheduler picks from the rest of the queue.
+    pub fn next(&mut self, deck: &mut Deck, now: DateTime<Utc>) -> Option<Item> {
+        let next = match self.mode {
+            StudyMode::Review => self
+                .take_learning(now)
+                .or_else(|| self.take_queued(deck, now))
+                .or_else(|| self.take_learning(now + Duration::minutes(LEARN_AHEAD_MINUTES))),
+            StudyMode::Cram => (!self.queue.is_empty()).then(|| self.queue.remove(0)),
+        };
+        self.current = next;
+        if let Some(item) = next {
+            self.started.get_or_insert(now);
+            deck.show(item);
+        }
+        next
+    }
+
+    //Grades the card on screen, putting it back in the session if it still has learning steps to go.
+    //The card is returned when its scheduling changed and it needs to be saved.
+    pub fn grade<'a>(
+        &mut self,
+        deck: &'a mut Deck,
+        grade: Grade,
+        now: DateTime<Utc>,
+    ) -> Option<&'a Card> {
+        let item = self.current.take()?;
+        self.answered += 1;
+        if grade == Grade::Again {
+            if !self.missed.contains(&item) {
+                self.missed.push(item);
+            }
+        } else {
+            self.correct += 1;
+        }
+        if self.mode == StudyMode::Cram {
+            if grade == Grade::Again {
+                self.queue.push(item);
+            }
+            return None;
+        }
+        deck.cur_card = item.card;
+        deck.cur_variant = item.variant;
+        let card = deck.grade_current(grade, now)?;
+        let schedule = card.schedule(item.variant);
+        if schedule.learning_step.is_some() {
+            if let Some(due) = schedule.due {
+                self.learning.push((item, due));
+            }
+        }
+        Some(card)
+    }
+
+    //Counts of new, learning and review cards left in the session, in that order.
+    pub fn counts(&self, deck: &Deck) -> (usize, usize, usize) {
+        let mut counts = (0, self.learning.len(), 0);
+        for item in self.queue.iter().chain(self.current.iter()) {
+            match deck.schedule(*item).state() {
+                CardState::New => counts.0 += 1,
+                CardState::Learning => counts.1 += 1,
+                CardState::Review => counts.2 += 1,
+            }
+        }
+        counts
+    }
+
+    fn take_learning(&mut self, before: DateTime<Utc>) -> Option<Item> {
+        let position = self
+            .learning
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, due))| *due <= before)
+            .min_by_key(|(_, (_, due))| *due)
+            .map(|(position, _)| position)?;
+        Some(self.learning.remove(position).0)
+    }
+
+    //Each deck's own scheduler picks from its cards, then the earliest due of those picks goes first.
+    fn take_queued(&mut self, deck: &Deck, now: DateTime<Utc>) -> Option<Item> {
+        let mut sources: Vec<usize> = self
+            .queue
+            .iter()
+            .map(|item| deck.contents[item.card].source)
+            .collect();
+        sources.sort();
+        sources.dedup();
+        let mut picks: Vec<usize> = Vec::new();
+        for source in sources {
+            let positions: Vec<usize> = (0..self.queue.len())
+                .filter(|position| deck.contents[self.queue[*position].card].source == source)
+                .collect();
+            let schedules: Vec<&Schedule> = positions
+                .iter()
+                .map(|position| deck.schedule(self.queue[*position]))
+                .collect();
+            let scheduler = deck
+                .card_settings(self.queue[positions[0]].card)
+                .scheduler();
+            if let Some(index) = scheduler.next(&schedules, now) {
+                picks.push(positions[index]);
+            }
+        }
+        let position = picks.into_iter().min_by_key(|position| {
+            let schedule = deck.schedule(self.queue[*position]);
+            (schedule.is_new(), schedule.due)
+        })?;
+        Some(self.queue.remove(position))
+    }
+}
+
+//A session left part way through, kept under the config directory until the deck is opened again.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedSession {
+    pub deck: String,
+    pub saved: DateTime<Utc>,
+    //The card ids in deck order, so a deck that changed in the meantime isn't resumed.
+    cards: Vec<Uuid>,
+    sections: Vec<usize>,
+    cur_card: usize,
+    cur_variant: Variant,
+    session: Session,
+}
+
+impl SavedSession {
+    pub fn new(deck: &Deck, session: &Session, now: DateTime<Utc>) -> Self {
+        SavedSession {
+            deck: deck.deck_title.clone(),
+            saved: now,
+            cards: deck.contents.iter().map(|card| card.id).collect(),
+            sections: deck
+                .contents
+                .iter()
+                .map(|card| card.current_section)
+                .collect(),
+            cur_card: deck.cur_card,
+            cur_variant: deck.cur_variant,
+            session: session.clone(),
+        }
+    }
+
+    fn file_path(config_directory: &Path, deck_title: &str) -> PathBuf {
+        config_directory
+            .join(SESSIONS_DIRECTORY)
+            .join(format!("{}.json", util::file_name(deck_title)))
+    }
+
+    pub fn write_to_dir(&self, config_directory: &Path) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(config_directory.join(SESSIONS_DIRECTORY))?;
+        fs::write(
+            SavedSession::file_path(config_directory, &self.deck),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+
+    //The saved session of a deck, as long as the deck still has the same cards.
+    pub fn read_from_dir(config_directory: &Path, deck: &Deck) -> Option<Self> {
+        let contents =
+            fs::read_to_string(SavedSession::file_path(config_directory, &deck.deck_title)).ok()?;
+        let saved: SavedSession = serde_json::from_str(&contents).ok()?;
+        saved
+            .cards
+            .iter()
+            .eq(deck.contents.iter().map(|card| &card.id))
+            .then_some(saved)
+    }
+
+    pub fn remove_from_dir(config_directory: &Path, deck_title: &str) {
+        let _ = fs::remove_file(SavedSession::file_path(config_directory, deck_title));
+    }
+
+    //Puts the deck back where the session was left and hands back the session.
+    pub fn restore(self, deck: &mut Deck) -> Session {
+        for (card, section) in deck.contents.iter_mut().zip(self.sections) {
+            card.current_section = section;
+        }
+        deck.cur_card = self.cur_card;
+        deck.cur_variant = self.cur_variant;
+        self.session
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{deck, item, start, temp_dir};
+
+    #[test]
+    fn summary_counts_graded_answers() {
+        let mut deck = deck();
+        let mut session = Session::new(&deck, start());
+        assert_eq!(session.accuracy(), None);
+        for grade in [Grade::Good, Grade::Again, Grade::Good, Grade::Good] {
+            session.next(&mut deck, start()).unwrap();
+            session.grade(&mut deck, grade, start()).unwrap();
+        }
+        assert_eq!(session.answered(), 4);
+        assert_eq!(session.accuracy(), Some(0.75));
+        assert_eq!(session.missed, vec![item(1)]);
+        assert_eq!(
+            session.elapsed(start() + Duration::minutes(5)),
+            Duration::minutes(5)
+        );
+        assert!(deck
+            .contents
+            .iter()
+            .all(|card| card.schedule.reviews.len() == 1));
+    }
+
+    #[test]
+    fn missed_cards_come_back_in_learning() {
+        let mut deck = deck();
+        let mut session = Session::new(&deck, start());
+        session.next(&mut deck, start());
+        session.grade(&mut deck, Grade::Again, start());
+        assert_eq!(session.next_pending(), Some(start() + Duration::minutes(1)));
+        assert_eq!(session.next(&mut deck, start()), Some(item(1)));
+        session.grade(&mut deck, Grade::Good, start());
+        for card in 2..4 {
+            assert_eq!(session.next(&mut deck, start()), Some(item(card)));
+            session.grade(&mut deck, Grade::Good, start());
+        }
+        //Nothing new is left, so the missed card is shown a little ahead of its step.
+        assert_eq!(session.next(&mut deck, start()), Some(item(0)));
+    }
+
+    #[test]
+    fn restudying_misses_leaves_schedules_alone() {
+        let mut deck = deck();
+        let mut session = Session::restudy(vec![item(1)]);
+        assert_eq!(session.next(&mut deck, start()), Some(item(1)));
+        assert!(session.grade(&mut deck, Grade::Again, start()).is_none());
+        assert_eq!(session.next(&mut deck, start()), Some(item(1)));
+        assert!(session.grade(&mut deck, Grade::Good, start()).is_none());
+        assert_eq!(session.next(&mut deck, start()), None);
+        assert!(session.is_finished());
+        assert_eq!(session.accuracy(), Some(0.5));
+        assert!(deck.schedule(item(1)).is_new());
+    }
+
+    #[test]
+    fn tag_sessions_save_under_a_plain_file_name() {
+        let config_directory = temp_dir();
+        let mut deck = deck();
+        deck.deck_title = String::from("#c++/stl");
+        let session = Session::new(&deck, start());
+        SavedSession::new(&deck, &session, start())
+            .write_to_dir(&config_directory)
+            .unwrap();
+        assert!(config_directory
+            .join(SESSIONS_DIRECTORY)
+            .join("#c++_stl.json")
+            .is_file());
+        assert!(SavedSession::read_from_dir(&config_directory, &deck).is_some());
+        SavedSession::remove_from_dir(&config_directory, &deck.deck_title);
+        assert!(SavedSession::read_from_dir(&config_directory, &deck).is_none());
+        fs::remove_dir_all(config_directory).unwrap();
+    }
+}
@@ -0,0 +1,434 @@
+use std::{error::Error, path::Path, rc::Rc};
+
+use chrono::{Duration, Utc};
+use ini::Ini;
+
+use crate::{
+    answer::AnswerMatching,
+    note_type::NoteType,
+    scheduler::{
+        Fsrs, LearningSteps, Leitner, Scheduler, Sm2, FSRS_DEFAULT_WEIGHTS,
+        LEITNER_DEFAULT_INTERVALS,
+    },
+};
+
+pub const SETTINGS_FILE: &str = "deck.ini";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchedulerKind {
+    Sm2,
+    Fsrs,
+    Leitner,
+}
+
+impl SchedulerKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "sm2" | "sm-2" => Some(SchedulerKind::Sm2),
+            "fsrs" => Some(SchedulerKind::Fsrs),
+            "leitner" => Some(SchedulerKind::Leitner),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchedulerKind::Sm2 => "sm2",
+            SchedulerKind::Fsrs => "fsrs",
+            SchedulerKind::Leitner => "leitner",
+        }
+    }
+}
+
+//How cards are lined up for a study session, manual follows the order saved with the deck.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CardOrder {
+    Manual,
+    Alphabetical,
+    Created,
+    Random,
+}
+
+impl CardOrder {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "manual" => Some(CardOrder::Manual),
+            "alphabetical" => Some(CardOrder::Alphabetical),
+            "created" => Some(CardOrder::Created),
+            "random" => Some(CardOrder::Random),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CardOrder::Manual => "manual",
+            CardOrder::Alphabetical => "alphabetical",
+            CardOrder::Created => "created",
+            CardOrder::Random => "random",
+        }
+    }
+}
+
+//Settings that live next to the cards in each deck directory, so they follow the deck around.
+#[derive(Clone, Debug)]
+pub struct DeckSettings {
+    pub scheduler: SchedulerKind,
+    pub desired_retention: f64,
+    pub fsrs_weights: Vec<f64>,
+    pub leitner_intervals: Vec<u32>,
+    pub new_cards_per_day: usize,
+    pub reviews_per_day: usize,
+    pub learning_steps: Vec<Duration>,
+    pub relearning_steps: Vec<Duration>,
+    pub leech_threshold: u32,
+    pub suspend_leeches: bool,
+    pub order: CardOrder,
+    //With a seed random order is the same on every machine, without one it changes every session.
+    pub seed: Option<u64>,
+    //Two section cards are also asked back to front.
+    pub bidirectional: bool,
+    pub answer_matching: AnswerMatching,
+    pub exam_time_limit: Duration,
+    pub exam_questions: usize,
+    pub matching_pairs: usize,
+    //Read from the [Note] and [Template ...] sections, which are left as written when saving.
+    pub note_type: Option<Rc<NoteType>>,
+}
+
+impl Default for DeckSettings {
+    fn default() -> Self {
+        DeckSettings {
+            scheduler: SchedulerKind::Sm2,
+            desired_retention: 0.9,
+            fsrs_weights: FSRS_DEFAULT_WEIGHTS.to_vec(),
+            leitner_intervals: LEITNER_DEFAULT_INTERVALS.to_vec(),
+            new_cards_per_day: 20,
+            reviews_per_day: 200,
+            learning_steps: vec![Duration::minutes(1), Duration::minutes(10)],
+            relearning_steps: vec![Duration::minutes(10)],
+            leech_threshold: 8,
+            suspend_leeches: true,
+            order: CardOrder::Manual,
+            seed: None,
+            bidirectional: false,
+            answer_matching: AnswerMatching::default(),
+            exam_time_limit: Duration::minutes(20),
+            exam_questions: 20,
+            matching_pairs: 6,
+            note_type: None,
+        }
+    }
+}
+
+impl DeckSettings {
+    //Missing files or values fall back to the defaults rather than failing to open the deck.
+    pub fn read_from_dir(dirpath: &Path) -> Self {
+        let mut settings = DeckSettings::default();
+        if let Ok(config) = Ini::load_from_file(dirpath.join(SETTINGS_FILE)) {
+            if let Some(section) = config.section(Some("Scheduler")) {
+                if let Some(kind) = section.get("algorithm").and_then(SchedulerKind::parse) {
+                    settings.scheduler = kind;
+                }
+                if let Some(retention) = section
+                    .get("desired_retention")
+                    .and_then(|value| value.trim().parse::<f64>().ok())
+                    .filter(|value| *value > 0.0 && *value < 1.0)
+                {
+                    settings.desired_retention = retention;
+                }
+                if let Some(weights) = section.get("weights").and_then(parse_list) {
+                    if weights.len() == FSRS_DEFAULT_WEIGHTS.len() {
+                        settings.fsrs_weights = weights;
+                    }
+                }
+            }
+            if let Some(intervals) = config
+                .section(Some("Leitner"))
+                .and_then(|section| section.get("box_intervals"))
+                .and_then(parse_list)
+                .filter(|intervals| !intervals.is_empty() && intervals.iter().all(|x| *x >= 1.0))
+            {
+                settings.leitner_intervals = intervals.iter().map(|x| x.round() as u32).collect();
+            }
+            if let Some(section) = config.section(Some("Steps")) {
+                if let Some(steps) = section.get("learning").and_then(parse_steps) {
+                    settings.learning_steps = steps;
+                }
+                if let Some(steps) = section.get("relearning").and_then(parse_steps) {
+                    settings.relearning_steps = steps;
+                }
+            }
+            if let Some(section) = config.section(Some("Leeches")) {
+                if let Some(threshold) = section
+                    .get("threshold")
+                    .and_then(parse_count)
+                    .filter(|threshold| *threshold > 0)
+                {
+                    settings.leech_threshold = threshold as u32;
+                }
+                if let Some(action) = section.get("action") {
+                    settings.suspend_leeches = action.trim().to_lowercase() != "tag";
+                }
+            }
+            if let Some(section) = config.section(Some("Study")) {
+                if let Some(order) = section.get("order").and_then(CardOrder::parse) {
+                    settings.order = order;
+                }
+                settings.seed = section
+                    .get("seed")
+                    .and_then(|seed| seed.trim().parse::<u64>().ok());
+                if let Some(value) = section.get("bidirectional").and_then(parse_bool) {
+                    settings.bidirectional = value;
+                }
+            }
+            if let Some(section) = config.section(Some("Typing")) {
+                let matching = &mut settings.answer_matching;
+                if let Some(value) = section.get("ignore_case").and_then(parse_bool) {
+                    matching.ignore_case = value;
+                }
+                if let Some(value) = section.get("ignore_whitespace").and_then(parse_bool) {
+                    matching.ignore_whitespace = value;
+                }
+                if let Some(value) = section.get("ignore_accents").and_then(parse_bool) {
+                    matching.ignore_accents = value;
+                }
+            }
+            if let Some(section) = config.section(Some("Exam")) {
+                if let Some(limit) = section
+                    .get("time_limit")
+                    .and_then(parse_steps)
+                    .filter(|steps| steps.len() == 1)
+                {
+                    settings.exam_time_limit = limit[0];
+                }
+                if let Some(questions) = section
+                    .get("questions")
+                    .and_then(parse_count)
+                    .filter(|questions| *questions > 0)
+                {
+                    settings.exam_questions = questions;
+                }
+            }
+            if let Some(pairs) = config
+                .section(Some("Matching"))
+                .and_then(|section| section.get("pairs"))
+                .and_then(parse_count)
+                .filter(|pairs| *pairs > 1)
+            {
+                settings.matching_pairs = pairs;
+            }
+            settings.note_type = NoteType::read(&config).map(Rc::new);
+            if let Some(section) = config.section(Some("Limits")) {
+                if let Some(limit) = section.get("new_per_day").and_then(parse_count) {
+                    settings.new_cards_per_day = limit;
+                }
+                if let Some(limit) = section.get("reviews_per_day").and_then(parse_count) {
+                    settings.reviews_per_day = limit;
+                }
+            }
+        }
+        settings
+    }
+
+    pub fn write_to_dir(&self, dirpath: &Path) -> Result<(), Box<dyn Error>> {
+        let file_path = dirpath.join(SETTINGS_FILE);
+        let mut config = Ini::load_from_file(&file_path).unwrap_or_default();
+        config
+            .with_section(Some("Scheduler"))
+            .set("algorithm", self.scheduler.name())
+            .set("desired_retention", self.desired_retention.to_string())
+            .set(
+                "weights",
+                self.fsrs_weights
+                    .iter()
+                    .map(|weight| format!("{:.4}", weight))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            );
+        config.with_section(Some("Leitner")).set(
+            "box_intervals",
+            self.leitner_intervals
+                .iter()
+                .map(|interval| interval.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+        );
+        config
+            .with_section(Some("Steps"))
+            .set("learning", format_steps(&self.learning_steps))
+            .set("relearning", format_steps(&self.relearning_steps));
+        config
+            .with_section(Some("Limits"))
+            .set("new_per_day", self.new_cards_per_day.to_string())
+            .set("reviews_per_day", self.reviews_per_day.to_string());
+        config
+            .with_section(Some("Leeches"))
+            .set("threshold", self.leech_threshold.to_string())
+            .set(
+                "action",
+                if self.suspend_leeches {
+                    "suspend"
+                } else {
+                    "tag"
+                },
+            );
+        config
+            .with_section(Some("Study"))
+            .set("order", self.order.name())
+            .set(
+                "seed",
+                self.seed.map(|seed| seed.to_string()).unwrap_or_default(),
+            )
+            .set("bidirectional", self.bidirectional.to_string());
+        config
+            .with_section(Some("Typing"))
+            .set("ignore_case", self.answer_matching.ignore_case.to_string())
+            .set(
+                "ignore_whitespace",
+                self.answer_matching.ignore_whitespace.to_string(),
+            )
+            .set(
+                "ignore_accents",
+                self.answer_matching.ignore_accents.to_string(),
+            );
+        config
+            .with_section(Some("Exam"))
+            .set("time_limit", format_steps(&[self.exam_time_limit]))
+            .set("questions", self.exam_questions.to_string());
+        config
+            .with_section(Some("Matching"))
+            .set("pairs", self.matching_pairs.to_string());
+        config.write_to_file(file_path)?;
+        Ok(())
+    }
+
+    pub fn steps(&self) -> LearningSteps {
+        LearningSteps::new(self.learning_steps.clone(), self.relearning_steps.clone())
+    }
+
+    pub fn scheduler(&self) -> Box<dyn Scheduler> {
+        match self.scheduler {
+            SchedulerKind::Sm2 => Box::new(Sm2),
+            SchedulerKind::Leitner => Box::new(Leitner::new(self.leitner_intervals.clone())),
+            SchedulerKind::Fsrs => {
+                Box::new(Fsrs::new(self.fsrs_weights.clone(), self.desired_retention))
+            }
+        }
+    }
+}
+
+fn parse_list(value: &str) -> Option<Vec<f64>> {
+    value
+        .split(',')
+        .map(|item| item.trim().parse::<f64>().ok())
+        .collect()
+}
+
+fn parse_count(value: &str) -> Option<usize> {
+    value.trim().parse::<usize>().ok()
+}
+
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+//Steps are written like "1m 10m 1h 1d", an empty value means no steps at all.
+pub fn parse_steps(value: &str) -> Option<Vec<Duration>> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|step| !step.is_empty())
+        .map(|step| {
+            let unit = step.chars().last()?;
+            let amount = step[..step.len() - unit.len_utf8()]
+                .parse::<i64>()
+                .ok()
+                .filter(|amount| *amount > 0)?;
+            let step = match unit {
+                's' => Duration::try_seconds(amount),
+                'm' => Duration::try_minutes(amount),
+                'h' => Duration::try_hours(amount),
+                'd' => Duration::try_days(amount),
+                _ => None,
+            }?;
+            Some(step).filter(is_reachable)
+        })
+        .collect()
+}
+
+//Steps are added to the current time, so one too large to land on a date is rejected like any other invalid step.
+pub fn is_reachable(step: &Duration) -> bool {
+    Utc::now().checked_add_signed(*step).is_some()
+}
+
+fn format_steps(steps: &[Duration]) -> String {
+    steps
+        .iter()
+        .map(|step| {
+            let seconds = step.num_seconds();
+            if seconds % 86400 == 0 {
+                format!("{}d", seconds / 86400)
+            } else if seconds % 3600 == 0 {
+                format!("{}h", seconds / 3600)
+            } else if seconds % 60 == 0 {
+                format!("{}m", seconds / 60)
+            } else {
+                format!("{}s", seconds)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn steps_take_a_unit_each() {
+        assert_eq!(
+            parse_steps("30s, 10m 1h,2d"),
+            Some(vec![
+                Duration::seconds(30),
+                Duration::minutes(10),
+                Duration::hours(1),
+                Duration::days(2)
+            ])
+        );
+        assert_eq!(parse_steps("10"), None);
+        assert_eq!(parse_steps("10m, 0m"), None);
+        assert_eq!(parse_steps("-5m"), None);
+        assert_eq!(parse_steps("5w"), None);
+    }
+
+    #[test]
+    fn steps_too_large_to_represent_are_invalid() {
+        assert_eq!(parse_steps("99999999999d"), None);
+        assert_eq!(parse_steps("10m, 9223372036854775807s"), None);
+        assert_eq!(parse_steps("999999999999999d"), None);
+    }
+
+    #[test]
+    fn invalid_steps_keep_the_defaults() {
+        let dirpath = temp_dir();
+        fs::write(
+            dirpath.join(SETTINGS_FILE),
+            "[Steps]\nlearning = 99999999999d\nrelearning = 5m",
+        )
+        .unwrap();
+        let settings = DeckSettings::read_from_dir(&dirpath);
+        assert_eq!(
+            settings.learning_steps,
+            DeckSettings::default().learning_steps
+        );
+        assert_eq!(settings.relearning_steps, vec![Duration::minutes(5)]);
+        fs::remove_dir_all(dirpath).unwrap();
+    }
+}
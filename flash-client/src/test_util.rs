@@ -0,0 +1,51 @@
+use std::{fs, path::PathBuf};
+
+use chrono::{DateTime, TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::{
+    card::{Card, Variant},
+    deck::{Deck, Item},
+};
+
+//Tests run at a fixed time so due dates and intervals come out the same every run.
+pub fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap()
+}
+
+pub fn card(title: &str, answer: &str) -> Card {
+    Card {
+        sections: vec![title.to_string(), answer.to_string()],
+        ..Card::new(title.to_string())
+    }
+}
+
+//Four new cards, each asking for a capital.
+pub fn deck() -> Deck {
+    Deck::new(
+        "Capitals",
+        [
+            ("France", "Paris"),
+            ("Spain", "Madrid"),
+            ("Italy", "Rome"),
+            ("Peru", "Lima"),
+        ]
+        .iter()
+        .map(|(title, answer)| card(title, answer))
+        .collect(),
+    )
+}
+
+//An empty folder for a test to write to, removed again by the test.
+pub fn temp_dir() -> PathBuf {
+    let dirpath = std::env::temp_dir().join(format!("flash-client-{}", Uuid::new_v4()));
+    fs::create_dir_all(&dirpath).unwrap();
+    dirpath
+}
+
+pub fn item(card: usize) -> Item {
+    Item {
+        card,
+        variant: Variant::Forward,
+    }
+}
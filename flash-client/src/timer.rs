@@ -0,0 +1,174 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use ini::Ini;
+
+use crate::settings::{is_reachable, parse_bool, parse_steps};
+
+pub const FOCUS_LOG_FILE: &str = "focus_log.json";
+
+//The study timer is set up in the [Timer] section of the global config.ini.
+#[derive(Clone, Debug)]
+pub struct TimerSettings {
+    pub enabled: bool,
+    pub work: Duration,
+    pub rest: Duration,
+}
+
+impl Default for TimerSettings {
+    fn default() -> Self {
+        TimerSettings {
+            enabled: false,
+            work: Duration::minutes(25),
+            rest: Duration::minutes(5),
+        }
+    }
+}
+
+impl TimerSettings {
+    pub fn read(config: &Ini) -> Self {
+        let mut settings = TimerSettings::default();
+        if let Some(section) = config.section(Some("Timer")) {
+            if let Some(enabled) = section.get("enabled").and_then(parse_bool) {
+                settings.enabled = enabled;
+            }
+            if let Some(work) = section.get("work").and_then(parse_duration) {
+                settings.work = work;
+            }
+            if let Some(rest) = section.get("break").and_then(parse_duration) {
+                settings.rest = rest;
+            }
+        }
+        settings
+    }
+}
+
+fn parse_duration(value: &str) -> Option<Duration> {
+    let mut steps = parse_steps(value)?.into_iter();
+    let first = steps.next()?;
+    steps
+        .try_fold(first, |total, step| total.checked_add(&step))
+        .filter(is_reachable)
+}
+
+//Counts the time spent studying and calls for a break once a work period is over.
+#[derive(Clone, Debug, Default)]
+pub struct StudyTimer {
+    pub settings: TimerSettings,
+    last_tick: Option<DateTime<Utc>>,
+    //Time studied in the current session and since the last break.
+    pub focused: Duration,
+    since_break: Duration,
+    //Time studied that hasn't been added to the focus log yet.
+    unlogged: Duration,
+    pub break_until: Option<DateTime<Utc>>,
+    //Seconds in today's focus log, read once when a break starts.
+    pub studied_today: i64,
+}
+
+impl StudyTimer {
+    pub fn new(settings: TimerSettings) -> Self {
+        StudyTimer {
+            settings,
+            ..StudyTimer::default()
+        }
+    }
+
+    pub fn start_session(&mut self) {
+        self.last_tick = None;
+        self.focused = Duration::zero();
+        self.since_break = Duration::zero();
+        self.break_until = None;
+    }
+
+    //Called every time around the event loop, returns true when it is time for a break.
+    pub fn tick(&mut self, studying: bool, now: DateTime<Utc>) -> bool {
+        if !self.settings.enabled || !studying {
+            self.last_tick = None;
+            return false;
+        }
+        if let Some(last_tick) = self.last_tick {
+            let step = now - last_tick;
+            self.focused += step;
+            self.since_break += step;
+            self.unlogged += step;
+        }
+        self.last_tick = Some(now);
+        if self.since_break >= self.settings.work {
+            self.since_break = Duration::zero();
+            self.last_tick = None;
+            self.break_until = Some(now + self.settings.rest);
+            return true;
+        }
+        false
+    }
+
+    pub fn until_break(&self) -> Duration {
+        self.settings.work - self.since_break
+    }
+
+    pub fn take_unlogged(&mut self) -> Duration {
+        std::mem::replace(&mut self.unlogged, Duration::zero())
+    }
+}
+
+//Total focused study time per day, kept in seconds as one JSON object keyed by date.
+pub struct FocusLog {
+    path: PathBuf,
+}
+
+impl FocusLog {
+    pub fn new(config_directory: &Path) -> Self {
+        FocusLog {
+            path: config_directory.join(FOCUS_LOG_FILE),
+        }
+    }
+
+    pub fn read(&self) -> BTreeMap<NaiveDate, i64> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn add(&self, day: NaiveDate, time: Duration) -> Result<(), Box<dyn Error>> {
+        if time <= Duration::zero() {
+            return Ok(());
+        }
+        let mut days = self.read();
+        *days.entry(day).or_default() += time.num_seconds();
+        fs::write(&self.path, serde_json::to_string_pretty(&days)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(text: &str) -> TimerSettings {
+        TimerSettings::read(&Ini::load_from_str(text).unwrap())
+    }
+
+    #[test]
+    fn periods_add_up_their_steps() {
+        let settings = read("[Timer]\nenabled = true\nwork = 1h 30m\nbreak = 10m");
+        assert!(settings.enabled);
+        assert_eq!(settings.work, Duration::minutes(90));
+        assert_eq!(settings.rest, Duration::minutes(10));
+    }
+
+    #[test]
+    fn periods_too_large_to_represent_are_ignored() {
+        let settings =
+            read("[Timer]\nwork = 99999999999d\nbreak = 9223372036854775s 9223372036854775s");
+        let defaults = TimerSettings::default();
+        assert_eq!(settings.work, defaults.work);
+        assert_eq!(settings.rest, defaults.rest);
+    }
+}
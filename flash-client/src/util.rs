@@ -1,4 +1,12 @@
-use std::{error::Error, ffi::OsString, fs::read_dir, path::Path};
+use std::{
+    error::Error,
+    ffi::OsString,
+    fs::{read_dir, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use serde::Serialize;
 
 pub fn get_sub_directories(parent_directory: &Path) -> Result<Vec<String>, Box<dyn Error>> {
     let entries = read_dir(parent_directory)?;
@@ -42,3 +50,51 @@ pub fn offset_state(
     );
     return new_state;
 }
+
+//The item shown on each line of a list drawn from a fresh state, scrolled the way tui scrolls it to keep the selection in view.
+pub fn visible_rows(heights: &[usize], selected: Option<usize>, max_height: usize) -> Vec<usize> {
+    if heights.is_empty() {
+        return Vec::new();
+    }
+    let mut start = 0;
+    let mut end = 0;
+    let mut height = 0;
+    for item_height in heights {
+        if height + item_height > max_height {
+            break;
+        }
+        height += item_height;
+        end += 1;
+    }
+    let selected = selected.unwrap_or(0).min(heights.len() - 1);
+    while selected >= end {
+        height += heights[end];
+        end += 1;
+        while height > max_height {
+            height -= heights[start];
+            start += 1;
+        }
+    }
+    let mut rows = Vec::new();
+    for (item, item_height) in heights.iter().enumerate().take(end).skip(start) {
+        rows.extend(std::iter::repeat_n(item, *item_height));
+    }
+    rows.truncate(max_height);
+    rows
+}
+
+//Deck titles can come from tags, which may hold path separators a file name can't.
+pub fn file_name(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| if std::path::is_separator(c) { '_' } else { c })
+        .collect()
+}
+
+//Appends one JSON record as a line of a log file and syncs it to disk.
+pub fn append_json_line<T: Serialize>(path: &Path, record: &T) -> Result<(), Box<dyn Error>> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    file.sync_data()?;
+    Ok(())
+}